@@ -2,22 +2,48 @@ use std::ops::Index;
 
 
 
+/// Genes are `f32` by default; other types (e.g. a fixed-point network's
+/// weights) can be stored and iterated over too, but the crossover and
+/// mutation methods work on `f32` only.
 #[derive(Clone, Debug)]
-pub struct Chromosome {
-    genes: Vec<f32>,
+pub struct Chromosome<T = f32> {
+    genes: Vec<T>,
+
+    /// Optional per-gene enable bits (e.g. which connections of a network
+    /// are active); same length as `genes`
+    mask: Option<Vec<bool>>,
 }
 
 
-impl Chromosome {
+impl<T> Chromosome<T> {
     pub fn len(&self) -> usize {
         self.genes.len()
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = &f32> {
+    pub fn is_empty(&self) -> bool {
+        self.genes.is_empty()
+    }
+
+    pub fn with_mask(mut self, mask: Vec<bool>) -> Self {
+        assert_eq!(mask.len(), self.genes.len());
+
+        self.mask = Some(mask);
+        self
+    }
+
+    pub fn mask(&self) -> Option<&[bool]> {
+        self.mask.as_deref()
+    }
+
+    pub fn mask_mut(&mut self) -> Option<&mut [bool]> {
+        self.mask.as_deref_mut()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
         self.genes.iter()
     }
 
-    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut f32> {
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
         self.genes.iter_mut()
     }
 }
@@ -26,37 +52,39 @@ impl Chromosome {
 impl PartialEq for Chromosome {
     fn eq(&self, other: &Self) -> bool {
         approx::relative_eq!(self.genes.as_slice(), other.genes.as_slice())
+            && self.mask == other.mask
     }
 }
 
-impl Index<usize> for Chromosome {
-    type Output = f32;
+impl<T> Index<usize> for Chromosome<T> {
+    type Output = T;
 
     fn index(&self, index: usize) -> &Self::Output {
         &self.genes[index]
     }
 }
 
-impl FromIterator<f32> for Chromosome {
-    fn from_iter<T: IntoIterator<Item = f32>>(iter: T) -> Self {
+impl<T> FromIterator<T> for Chromosome<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
         Self {
             genes: iter.into_iter().collect(),
+            mask: None,
         }
     }
 }
 
-impl IntoIterator for Chromosome {
-    type Item = f32;
-    type IntoIter = std::vec::IntoIter<f32>;
+impl<T> IntoIterator for Chromosome<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.genes.into_iter() //because being consumed
     }
 }
 
-impl<'a> IntoIterator for &'a Chromosome {
-    type Item = &'a f32;
-    type IntoIter = std::slice::Iter<'a, f32>;
+impl<'a, T> IntoIterator for &'a Chromosome<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.genes.iter() //because not being consumed
@@ -71,6 +99,7 @@ mod tests {
     fn chromosome() -> Chromosome {
         Chromosome {
             genes: vec![3.0, 1.0, 2.0],
+            mask: None,
         }
     }
 
@@ -125,6 +154,7 @@ mod tests {
         fn test() {
             let chromosome = Chromosome {
                 genes: vec![3.0, 1.0, 2.0],
+                mask: None,
             };
 
             assert_eq!(chromosome[0], 3.0);
@@ -133,6 +163,28 @@ mod tests {
         }
     }
 
+    mod mask {
+        use super::*;
+
+        #[test]
+        fn defaults_to_none() {
+            assert_eq!(chromosome().mask(), None);
+        }
+
+        #[test]
+        fn with_mask() {
+            let chromosome = chromosome().with_mask(vec![true, false, true]);
+
+            assert_eq!(chromosome.mask(), Some(&[true, false, true][..]));
+        }
+
+        #[test]
+        #[should_panic]
+        fn with_mask_of_wrong_length() {
+            chromosome().with_mask(vec![true]);
+        }
+    }
+
     mod from_iterator {
         use super::*;
 
@@ -147,6 +199,14 @@ mod tests {
             assert_eq!(chromosome[1], 1.0);
             assert_eq!(chromosome[2], 2.0);
         }
+
+        #[test]
+        fn other_gene_types() {
+            let chromosome: Chromosome<i32> = vec![3, 1, 2].into_iter().collect();
+
+            assert_eq!(chromosome.len(), 3);
+            assert_eq!(chromosome.into_iter().collect::<Vec<_>>(), vec![3, 1, 2]);
+        }
     }
 
 
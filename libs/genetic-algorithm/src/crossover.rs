@@ -1,4 +1,4 @@
-use rand::{Rng, RngCore};
+use rand::{seq::index, Rng, RngCore};
 
 use crate::chromosome::Chromosome;
 
@@ -7,7 +7,7 @@ pub trait CrossoverMethod {
     fn crossover(&self, rng: &mut dyn RngCore, parent_a: &Chromosome, parent_b: &Chromosome) -> Chromosome;
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct UniformCrossover;
 
 impl UniformCrossover {
@@ -18,19 +18,209 @@ impl UniformCrossover {
 
 impl CrossoverMethod for UniformCrossover {
     fn crossover(&self, rng: &mut dyn RngCore, parent_a: &Chromosome, parent_b: &Chromosome) -> Chromosome{
+        pick_genes(parent_a, parent_b, |_| rng.gen_bool(0.5))
+    }
+}
+
+/// Cuts both parents at the same `points` random places and takes the
+/// pieces alternately from each of them - so, unlike `UniformCrossover`,
+/// genes lying next to each other (e.g. weights of a single neuron) mostly
+/// stay together.
+///
+/// Single-point crossover is `KPointCrossover::new(1)`; with more points
+/// than there are places to cut, the chromosome gets cut everywhere.
+#[derive(Clone, Debug)]
+pub struct KPointCrossover {
+    points: usize,
+}
+
+impl KPointCrossover {
+    pub fn new(points: usize) -> Self {
+        assert!(points > 0);
+
+        Self { points }
+    }
+}
+
+impl CrossoverMethod for KPointCrossover {
+    fn crossover(&self, rng: &mut dyn RngCore, parent_a: &Chromosome, parent_b: &Chromosome) -> Chromosome {
         assert_eq!(parent_a.len(), parent_b.len());
 
-        parent_a.iter()
-        .zip(parent_b)
-        .map(|(&a, &b)| if rng.gen_bool(0.5) { a } else { b })
-        .collect()
+        // Cutting before the first gene wouldn't change anything
+        let places = parent_a.len().saturating_sub(1);
+
+        let mut cuts: Vec<usize> = index::sample(rng, places, self.points.min(places))
+            .into_iter()
+            .map(|cut| cut + 1)
+            .collect();
+
+        cuts.sort_unstable();
+
+        pick_genes(parent_a, parent_b, |idx| cuts.partition_point(|&cut| cut <= idx) % 2 == 0)
+    }
+}
+
+/// Child is a weighted average of its parents: `alpha * a + (1 - alpha) * b`,
+/// with `alpha` drawn from <0, 1> for every child, unless it's fixed with
+/// `with_alpha()`.
+#[derive(Clone, Debug, Default)]
+pub struct ArithmeticCrossover {
+    alpha: Option<f32>,
+}
+
+impl ArithmeticCrossover {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_alpha(mut self, alpha: f32) -> Self {
+        assert!((0.0..=1.0).contains(&alpha));
+
+        self.alpha = Some(alpha);
+        self
+    }
+}
+
+impl CrossoverMethod for ArithmeticCrossover {
+    fn crossover(&self, rng: &mut dyn RngCore, parent_a: &Chromosome, parent_b: &Chromosome) -> Chromosome {
+        let alpha = self.alpha.unwrap_or_else(|| rng.gen_range(0.0..=1.0));
+
+        blend_genes(parent_a, parent_b, |a, b| alpha * a + (1.0 - alpha) * b)
+    }
+}
+
+/// BLX-alpha: each gene is drawn uniformly from the range spanned by the
+/// parents' genes, extended by `alpha` times its width on both sides - so
+/// the child can land a bit outside of its parents, too.
+#[derive(Clone, Debug)]
+pub struct BlendCrossover {
+    alpha: f32,
+}
+
+impl BlendCrossover {
+    pub fn new(alpha: f32) -> Self {
+        assert!(alpha >= 0.0);
+
+        Self { alpha }
+    }
+}
+
+impl Default for BlendCrossover {
+    fn default() -> Self {
+        Self::new(0.5)
+    }
+}
+
+impl CrossoverMethod for BlendCrossover {
+    fn crossover(&self, rng: &mut dyn RngCore, parent_a: &Chromosome, parent_b: &Chromosome) -> Chromosome {
+        blend_genes(parent_a, parent_b, |a, b| {
+            let extension = self.alpha * (a - b).abs();
+            let min = a.min(b) - extension;
+            let max = a.max(b) + extension;
+
+            if min < max {
+                rng.gen_range(min..=max)
+            } else {
+                a
+            }
+        })
+    }
+}
+
+/// Simulated binary crossover (SBX): mimics single-point crossover of
+/// binary-encoded genes - children are spread around their parents, the
+/// more tightly the larger the distribution index `eta` is.
+#[derive(Clone, Debug)]
+pub struct SimulatedBinaryCrossover {
+    eta: f32,
+}
+
+impl SimulatedBinaryCrossover {
+    pub fn new(eta: f32) -> Self {
+        assert!(eta >= 0.0);
+
+        Self { eta }
+    }
+}
+
+impl Default for SimulatedBinaryCrossover {
+    fn default() -> Self {
+        Self::new(2.0)
+    }
+}
+
+impl CrossoverMethod for SimulatedBinaryCrossover {
+    fn crossover(&self, rng: &mut dyn RngCore, parent_a: &Chromosome, parent_b: &Chromosome) -> Chromosome {
+        let exponent = 1.0 / (self.eta + 1.0);
+
+        blend_genes(parent_a, parent_b, |a, b| {
+            let u: f32 = rng.gen();
+
+            let beta = if u <= 0.5 {
+                (2.0 * u).powf(exponent)
+            } else {
+                (1.0 / (2.0 * (1.0 - u))).powf(exponent)
+            };
+
+            // SBX makes two children, symmetric around the parents' mean;
+            // every gene comes from one of them at random
+            let spread = if rng.gen_bool(0.5) { beta } else { -beta };
+
+            0.5 * ((a + b) + spread * (a - b))
+        })
+    }
+}
+
+// Builds a child out of its parents' genes, taking the `idx`-th one from
+// `parent_a` if `from_a(idx)` says so
+fn pick_genes(parent_a: &Chromosome, parent_b: &Chromosome, mut from_a: impl FnMut(usize) -> bool) -> Chromosome {
+    assert_eq!(parent_a.len(), parent_b.len());
+
+    // Mask bits are inherited together with their genes; a parent
+    // without a mask counts as having all of them enabled
+    let mut mask = Vec::with_capacity(parent_a.len());
+
+    let child: Chromosome = parent_a.iter()
+    .zip(parent_b)
+    .enumerate()
+    .map(|(idx, (&a, &b))| {
+        let (gene, parent) = if from_a(idx) { (a, parent_a) } else { (b, parent_b) };
+
+        mask.push(parent.mask().is_none_or(|bits| bits[idx]));
+        gene
+    })
+    .collect();
+
+    with_mask(child, parent_a, parent_b, mask)
+}
+
+// Builds a child whose genes are mixtures of the parents' ones
+fn blend_genes(parent_a: &Chromosome, parent_b: &Chromosome, mut blend: impl FnMut(f32, f32) -> f32) -> Chromosome {
+    assert_eq!(parent_a.len(), parent_b.len());
+
+    let child: Chromosome = parent_a.iter().zip(parent_b).map(|(&a, &b)| blend(a, b)).collect();
+
+    // A blended gene belongs to both parents, so it's enabled if it's been
+    // enabled in either of them
+    let mask = (0..parent_a.len())
+        .map(|idx| {
+            parent_a.mask().is_none_or(|bits| bits[idx]) || parent_b.mask().is_none_or(|bits| bits[idx])
+        })
+        .collect();
+
+    with_mask(child, parent_a, parent_b, mask)
+}
+
+fn with_mask(child: Chromosome, parent_a: &Chromosome, parent_b: &Chromosome, mask: Vec<bool>) -> Chromosome {
+    if parent_a.mask().is_some() || parent_b.mask().is_some() {
+        child.with_mask(mask)
+    } else {
+        child
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-
     mod uniform_crossover{
         use rand::SeedableRng;
         use rand_chacha::ChaCha8Rng;
@@ -64,6 +254,252 @@ mod tests {
             assert_eq!(diff_a, 49);
             assert_eq!(diff_b, 51);
         }
+
+        #[test]
+        fn inherits_mask_bits_with_genes() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let parent_a: Chromosome = (1..=100).map(|n| n as f32).collect();
+            let parent_a = parent_a.with_mask(vec![true; 100]);
+            let parent_b: Chromosome = (1..=100).map(|n| -n as f32).collect();
+            let parent_b = parent_b.with_mask(vec![false; 100]);
+
+            let child = UniformCrossover::new()
+                .crossover(&mut rng, &parent_a, &parent_b);
+
+            for (gene, &enabled) in child.iter().zip(child.mask().unwrap()) {
+                assert_eq!(*gene > 0.0, enabled);
+            }
+        }
+    }
+
+    mod k_point_crossover {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use crate::{chromosome::Chromosome, crossover::{CrossoverMethod, KPointCrossover}};
+
+        // Indices at which the child switches from one parent to the other
+        fn cuts(child: &Chromosome) -> Vec<usize> {
+            (1..child.len())
+                .filter(|&idx| (child[idx] > 0.0) != (child[idx - 1] > 0.0))
+                .collect()
+        }
+
+        #[test]
+        fn test() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let parent_a: Chromosome = (1..=100).map(|n| n as f32).collect();
+            let parent_b: Chromosome = (1..=100).map(|n| -n as f32).collect();
+
+            let child = KPointCrossover::new(3)
+                .crossover(&mut rng, &parent_a, &parent_b);
+
+            // Number of genes different between `child` and `parent_a`
+            let diff_a = child
+            .iter()
+            .zip(parent_a)
+            .filter(|(c, p)| *c != p)
+            .count();
+
+            assert_eq!(cuts(&child), [19, 63, 83]);
+            assert_eq!(diff_a, 61);
+            assert!(child[0] > 0.0, "the first piece comes from `parent_a`");
+        }
+
+        #[test]
+        fn single_point() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let parent_a: Chromosome = (1..=100).map(|n| n as f32).collect();
+            let parent_b: Chromosome = (1..=100).map(|n| -n as f32).collect();
+
+            let child = KPointCrossover::new(1)
+                .crossover(&mut rng, &parent_a, &parent_b);
+
+            assert_eq!(cuts(&child), [19]);
+        }
+
+        #[test]
+        fn more_points_than_genes() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let parent_a: Chromosome = [1.0, 2.0, 3.0, 4.0].into_iter().collect();
+            let parent_b: Chromosome = [-1.0, -2.0, -3.0, -4.0].into_iter().collect();
+
+            let child = KPointCrossover::new(10)
+                .crossover(&mut rng, &parent_a, &parent_b);
+
+            let expected: Chromosome = [1.0, -2.0, 3.0, -4.0].into_iter().collect();
+
+            assert_eq!(child, expected);
+        }
+
+        #[test]
+        fn inherits_mask_bits_with_genes() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let parent_a: Chromosome = (1..=100).map(|n| n as f32).collect();
+            let parent_b: Chromosome = (1..=100).map(|n| -n as f32).collect();
+            let parent_b = parent_b.with_mask(vec![false; 100]);
+
+            let child = KPointCrossover::new(5)
+                .crossover(&mut rng, &parent_a, &parent_b);
+
+            for (gene, &enabled) in child.iter().zip(child.mask().unwrap()) {
+                assert_eq!(*gene > 0.0, enabled);
+            }
+        }
+    }
+
+    mod arithmetic_crossover {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use crate::{chromosome::Chromosome, crossover::{ArithmeticCrossover, CrossoverMethod}};
+
+        #[test]
+        fn fixed_alpha() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let parent_a: Chromosome = [1.0, 2.0, -4.0].into_iter().collect();
+            let parent_b: Chromosome = [5.0, 2.0, 4.0].into_iter().collect();
+
+            let child = ArithmeticCrossover::new()
+                .with_alpha(0.25)
+                .crossover(&mut rng, &parent_a, &parent_b);
+
+            let expected: Chromosome = [4.0, 2.0, 2.0].into_iter().collect();
+
+            assert_eq!(child, expected);
+        }
+
+        #[test]
+        fn random_alpha() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let parent_a: Chromosome = (1..=100).map(|n| n as f32).collect();
+            let parent_b: Chromosome = (1..=100).map(|n| -n as f32).collect();
+
+            let child = ArithmeticCrossover::new()
+                .crossover(&mut rng, &parent_a, &parent_b);
+
+            // Every gene is `n * (2 * alpha - 1)`, for the same alpha
+            let alpha = (child[0] + 1.0) / 2.0;
+
+            approx::assert_relative_eq!(alpha, 0.1872406);
+
+            for (n, gene) in child.iter().enumerate() {
+                approx::assert_relative_eq!(*gene, (n + 1) as f32 * (2.0 * alpha - 1.0), epsilon = 1e-4);
+            }
+        }
+
+        #[test]
+        fn enables_genes_enabled_in_either_parent() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let parent_a: Chromosome = [1.0, 2.0, 3.0].into_iter().collect();
+            let parent_a = parent_a.with_mask(vec![true, false, false]);
+            let parent_b: Chromosome = [1.0, 2.0, 3.0].into_iter().collect();
+            let parent_b = parent_b.with_mask(vec![false, true, false]);
+
+            let child = ArithmeticCrossover::new()
+                .crossover(&mut rng, &parent_a, &parent_b);
+
+            assert_eq!(child.mask(), Some([true, true, false].as_ref()));
+        }
+    }
+
+    mod blend_crossover {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use crate::{chromosome::Chromosome, crossover::{BlendCrossover, CrossoverMethod}};
+
+        #[test]
+        fn test() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let parent_a: Chromosome = (1..=100).map(|n| n as f32).collect();
+            let parent_b: Chromosome = (1..=100).map(|n| -n as f32).collect();
+
+            let child = BlendCrossover::new(0.5)
+                .crossover(&mut rng, &parent_a, &parent_b);
+
+            // The range is `[-n, n]`, extended by `n` on both sides
+            for (n, gene) in child.iter().enumerate() {
+                assert!(gene.abs() <= 2.0 * (n + 1) as f32);
+            }
+
+            // Number of genes that landed outside of their parents' range
+            let outside = child
+            .iter()
+            .enumerate()
+            .filter(|(n, gene)| gene.abs() > (n + 1) as f32)
+            .count();
+
+            assert_eq!(outside, 40);
+        }
+
+        #[test]
+        fn identical_parents() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let parent: Chromosome = (1..=100).map(|n| n as f32).collect();
+
+            let child = BlendCrossover::default()
+                .crossover(&mut rng, &parent, &parent);
+
+            assert_eq!(child, parent);
+        }
     }
-    
-}
\ No newline at end of file
+
+    mod simulated_binary_crossover {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use crate::{chromosome::Chromosome, crossover::{CrossoverMethod, SimulatedBinaryCrossover}};
+
+        // How far every gene landed from the parents' mean, relative to
+        // the parents' own distance from it
+        fn spreads(eta: f32) -> Vec<f32> {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let parent_a: Chromosome = (1..=100).map(|n| n as f32).collect();
+            let parent_b: Chromosome = (1..=100).map(|n| -n as f32).collect();
+
+            let child = SimulatedBinaryCrossover::new(eta)
+                .crossover(&mut rng, &parent_a, &parent_b);
+
+            child
+            .iter()
+            .enumerate()
+            .map(|(n, gene)| gene.abs() / (n + 1) as f32)
+            .collect()
+        }
+
+        #[test]
+        fn test() {
+            // Number of genes that landed outside of their parents' range
+            let outside = spreads(2.0)
+            .into_iter()
+            .filter(|&spread| spread > 1.0)
+            .count();
+
+            assert_eq!(outside, 53);
+        }
+
+        #[test]
+        fn larger_eta_stays_closer_to_parents() {
+            let distance = |eta| {
+                spreads(eta)
+                    .into_iter()
+                    .map(|spread| (spread - 1.0).abs())
+                    .sum::<f32>()
+            };
+
+            assert!(distance(20.0) < distance(2.0) / 4.0);
+        }
+
+        #[test]
+        fn identical_parents() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let parent: Chromosome = (1..=100).map(|n| n as f32).collect();
+
+            let child = SimulatedBinaryCrossover::default()
+                .crossover(&mut rng, &parent, &parent);
+
+            assert_eq!(child, parent);
+        }
+    }
+}
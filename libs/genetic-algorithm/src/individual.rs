@@ -4,6 +4,24 @@ pub trait Individual {
     fn fitness(&self) -> f32;
     fn chromosome(&self) -> &Chromosome;
     fn create(chromosome: Chromosome) -> Self;
+
+    /// How different this individual is from another one - used for the
+    /// population's diversity (see `Statistics`) and for `FitnessSharing`.
+    ///
+    /// Defaults to the Euclidean distance between chromosomes; individuals
+    /// carrying a neural network may prefer one of the network's own
+    /// metrics instead (e.g. a behavioural one).
+    fn distance(&self, other: &Self) -> f32
+    where
+        Self: Sized,
+    {
+        self.chromosome()
+            .iter()
+            .zip(other.chromosome().iter())
+            .map(|(a, b)| (a - b).powi(2))
+            .sum::<f32>()
+            .sqrt()
+    }
 }
 
 #[cfg(test)]
@@ -1,7 +1,8 @@
 use std::cmp::Ordering;
 
 pub use self::{
-    chromosome::*, crossover::*, individual::*, mutation::*, selection::*,
+    chromosome::*, crossover::*, individual::*, mutation::*, niching::*, replacement::*,
+    selection::*,
 };
 
 use rand::RngCore;
@@ -9,6 +10,8 @@ use rand::RngCore;
 mod chromosome;
 mod crossover;
 mod individual;
+mod niching;
+mod replacement;
 mod selection;
 mod mutation;
 
@@ -16,6 +19,10 @@ pub struct GeneticAlgorithm<S> {
     selection_method: S,
     crossover_method: Box<dyn CrossoverMethod>,
     mutation_method: Box<dyn MutationMethod>,
+    fitness_sharing: Option<FitnessSharing>,
+    replacement: Replacement,
+    generation: usize,
+    diversity: bool,
 }
 
 impl<S> GeneticAlgorithm<S>
@@ -23,36 +30,114 @@ where
     S: SelectionMethod,
 {
     pub fn new(selection_method: S, crossover_method: impl CrossoverMethod + 'static, mutation_method: impl MutationMethod + 'static) -> Self {
-        Self { selection_method, crossover_method: Box::new(crossover_method), mutation_method: Box::new(mutation_method), }
+        Self { selection_method, crossover_method: Box::new(crossover_method), mutation_method: Box::new(mutation_method), fitness_sharing: None, replacement: Replacement::default(), generation: 0, diversity: false, }
     }
 
-    pub fn evolve<I>(&mut self, rng: &mut dyn RngCore, mut population: &mut [I]) -> (Vec<I>, Statistics)
+    pub fn with_replacement(mut self, replacement: Replacement) -> Self {
+        replacement.validate();
+
+        self.replacement = replacement;
+        self
+    }
+
+    /// Makes parents get selected by their shared fitness (see
+    /// `FitnessSharing`) instead of the raw one, so that the population
+    /// spreads over many niches rather than crowding around a single peak.
+    pub fn with_fitness_sharing(mut self, fitness_sharing: FitnessSharing) -> Self {
+        self.fitness_sharing = Some(fitness_sharing);
+        self
+    }
+
+    /// Makes `Statistics` report the population's diversity - which means
+    /// measuring the distance between every pair of individuals, so it's
+    /// off by default (unless fitness sharing, which is after the very same
+    /// distances, is enabled).
+    pub fn with_diversity(mut self) -> Self {
+        self.diversity = true;
+        self
+    }
+
+    /// Number of generations evolved so far.
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    pub fn evolve<I>(&mut self, rng: &mut dyn RngCore, population: &[I]) -> (Vec<I>, Statistics)
     where
         I: Individual + Clone,
     {
         assert!(!population.is_empty());
-        self.selection_method.set_not_sorted_population();
-
-        let new_population = (0..population.len())
-            .map(|_| {
-                self.selection_method.sort(&mut population);
+        self.replacement.validate_for(population.len());
 
-                let parent_a = self.selection_method.select(rng, population).chromosome();
+        let (survivors, parents, children) = self.replacement.plan(population.len());
+        let ranking = if survivors > 0 || parents.is_some() { ranking(population) } else { Vec::new() };
 
-                let parent_b = self.selection_method.select(rng, population).chromosome();
+        // Parents come from the whole population, unless the replacement
+        // says otherwise
+        let children = match parents {
+            Some(parents) => {
+                let pool: Vec<I> = ranking[..parents].iter().map(|&idx| population[idx].clone()).collect();
 
-                let mut child = self.crossover_method.crossover(rng, parent_a, parent_b);
-                
-                self.mutation_method.mutate(rng, &mut child);
+                self.breed(rng, &pool, children)
+            }
+            None => self.breed(rng, population, children),
+        };
 
-                I::create(child)
+        let stats = Statistics::new(population, self.diversity || self.fitness_sharing.is_some());
 
-            })
+        let new_population = ranking[..survivors]
+            .iter()
+            .map(|&idx| population[idx].clone())
+            .chain(children.into_iter().map(I::create))
             .collect();
-        let stats = Statistics::new(population);
+
+        self.generation += 1;
 
         (new_population, stats)
     }
+
+    fn breed<I>(&self, rng: &mut dyn RngCore, population: &[I], children: usize) -> Vec<Chromosome>
+    where
+        I: Individual + Clone,
+    {
+        match &self.fitness_sharing {
+            Some(fitness_sharing) => {
+                let shared = fitness_sharing.apply(population);
+
+                self.reproduce(rng, &shared, children)
+            }
+            None => self.reproduce(rng, population, children),
+        }
+    }
+
+    // Selects, crosses over and mutates given number of children
+    fn reproduce<I>(&self, rng: &mut dyn RngCore, population: &[I], children: usize) -> Vec<Chromosome>
+    where
+        I: Individual,
+    {
+        let selector = self.selection_method.prepare(population, self.generation);
+
+        // Methods like `StochasticUniversalSampling` pick all parents in a
+        // single pass; others pick them as they go, two per child
+        let mut parents = selector
+            .selects_jointly()
+            .then(|| selector.select_many(rng, 2 * children).into_iter());
+
+        (0..children)
+            .map(|_| {
+                let (parent_a, parent_b) = match &mut parents {
+                    Some(parents) => (parents.next().unwrap(), parents.next().unwrap()),
+                    None => (selector.select(rng), selector.select(rng)),
+                };
+
+                let mut child = self.crossover_method.crossover(rng, parent_a.chromosome(), parent_b.chromosome());
+
+                self.mutation_method.mutate(rng, &mut child);
+
+                child
+            })
+            .collect()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -61,10 +146,12 @@ pub struct Statistics {
     max_fitness: f32,
     avg_fitness: f32,
     median_fitness: f32,
+    diversity: Option<f32>,
+    discarded_children: usize,
 }
 
 impl Statistics {
-    fn new<I>(population: &[I]) -> Self
+    fn new<I>(population: &[I], diversity: bool) -> Self
     where
         I: Individual + Clone,
     {
@@ -74,10 +161,10 @@ impl Statistics {
         let mut max_fitness = min_fitness;
         let mut sum_fitness = 0.0;
         
-        let mut sorted_population = population.clone().to_vec();
+        let mut sorted_population = population.to_vec();
         sorted_population.sort_by(|a, b| a.fitness().partial_cmp(&b.fitness()).unwrap_or(Ordering::Equal));
 
-        let median_fitness = if sorted_population.len() % 2 == 0 {
+        let median_fitness = if sorted_population.len().is_multiple_of(2) {
             (sorted_population[sorted_population.len() / 2].fitness() + sorted_population[sorted_population.len() / 2 - 1].fitness()) / 2.0
         } else {
             sorted_population[sorted_population.len() / 2].fitness()
@@ -96,9 +183,18 @@ impl Statistics {
             max_fitness,
             avg_fitness: sum_fitness / (population.len() as f32),
             median_fitness,
+            diversity: diversity.then(|| self::diversity(population)),
+            discarded_children: 0,
         }
     }
 
+    /// Records how many of the children the caller has discarded - e.g.
+    /// ones whose chromosome doesn't make up a valid individual.
+    pub fn with_discarded_children(mut self, discarded_children: usize) -> Self {
+        self.discarded_children = discarded_children;
+        self
+    }
+
     pub fn min_fitness(&self) -> f32 {
         self.min_fitness
     }
@@ -113,6 +209,19 @@ impl Statistics {
     pub fn median_fitness(&self) -> f32 {
         self.median_fitness
     }
+
+    /// Average `Individual::distance()` between two individuals of the
+    /// population (zero if it's got just one); `None` unless enabled with
+    /// `GeneticAlgorithm::with_diversity()` or fitness sharing.
+    pub fn diversity(&self) -> Option<f32> {
+        self.diversity
+    }
+
+    /// See `with_discarded_children()`; the algorithm itself never discards
+    /// any, so it's zero unless the caller says otherwise.
+    pub fn discarded_children(&self) -> usize {
+        self.discarded_children
+    }
 }
 
 #[cfg(test)]
@@ -130,6 +239,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::excessive_precision)]
     fn test_with_roulette_wheel_selection() {
         let mut rng = ChaCha8Rng::from_seed(Default::default());
 
@@ -155,7 +265,7 @@ mod tests {
         // that'd change is the *magnitude* of difference between
         // initial and output population.
         for _ in 0..10 {
-            (population, _) = ga.evolve(&mut rng, &mut population);
+            (population, _) = ga.evolve(&mut rng, &population);
         }
 
         let expected_population = vec![
@@ -168,6 +278,40 @@ mod tests {
         assert_eq!(population, expected_population);
     }
     
+    #[test]
+    fn test_with_fitness_sharing() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        let mut ga = GeneticAlgorithm::new(
+            RouletteWheelSelection::new(),
+            UniformCrossover::new(),
+            GaussianMutation::new(0.5, 0.5),
+        )
+        .with_fitness_sharing(FitnessSharing::new(1.0));
+
+        let mut population = vec![
+            individual(&[0.0, 0.0, 0.0]),
+            individual(&[1.0, 1.0, 1.0]),
+            individual(&[1.0, 2.0, 1.0]),
+            individual(&[1.0, 2.0, 4.0]),
+        ];
+
+        for _ in 0..10 {
+            (population, _) = ga.evolve(&mut rng, &population);
+        }
+
+        // Mostly the same as without sharing, except for the second and
+        // third individual's last genes
+        let expected_population = vec![
+            individual(&[0.4476949, 2.0648358, 4.3058133]),
+            individual(&[1.2126867, 1.5538777, 4.9813137]),
+            individual(&[1.0617678, 2.265739, 4.380532]),
+            individual(&[0.95909685, 2.4618788, 4.024733]),
+        ];
+
+        assert_eq!(population, expected_population);
+    }
+
     #[test]
     fn test_with_rank_selection() {
         let mut rng = ChaCha8Rng::from_seed(Default::default());
@@ -193,7 +337,7 @@ mod tests {
         // that'd change is the *magnitude* of difference between
         // initial and output population.
         for _ in 0..10 {
-            (population, _) = ga.evolve(&mut rng, &mut population);
+            (population, _) = ga.evolve(&mut rng, &population);
         }
 
         //let expected_population = vec![
@@ -212,4 +356,137 @@ mod tests {
 
         assert_eq!(population, expected_population);
     }
+
+    mod replacement {
+        use super::*;
+        use crate::selection::TournamentSelection;
+
+        // Fitness is the sum of genes, so it's 4, 0, 8, 2 and 6
+        fn population() -> Vec<TestIndividual> {
+            vec![
+                individual(&[2.0, 2.0]),
+                individual(&[0.0, 0.0]),
+                individual(&[4.0, 4.0]),
+                individual(&[1.0, 1.0]),
+                individual(&[3.0, 3.0]),
+            ]
+        }
+
+        // Without mutation, children are made of their parents' genes only
+        fn evolve(replacement: Replacement) -> Vec<TestIndividual> {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+            let mut ga = GeneticAlgorithm::new(
+                TournamentSelection::new(2),
+                UniformCrossover::new(),
+                GaussianMutation::new(0.0, 0.0),
+            )
+            .with_replacement(replacement);
+
+            let (population, _) = ga.evolve(&mut rng, &population());
+
+            assert_eq!(population.len(), 5);
+            population
+        }
+
+        fn genes(population: &[TestIndividual]) -> Vec<f32> {
+            population
+                .iter()
+                .flat_map(|individual| individual.chromosome().iter().copied())
+                .collect()
+        }
+
+        #[test]
+        fn elitism() {
+            let population = evolve(Replacement::Elitism { elites: 2 });
+
+            assert_eq!(population[..2], [individual(&[4.0, 4.0]), individual(&[3.0, 3.0])]);
+        }
+
+        #[test]
+        fn mu_plus_lambda() {
+            let population = evolve(Replacement::MuPlusLambda { mu: 2 });
+
+            assert_eq!(population[..2], [individual(&[4.0, 4.0]), individual(&[3.0, 3.0])]);
+
+            for gene in genes(&population[2..]) {
+                assert!(gene == 3.0 || gene == 4.0, "{} doesn't come from the best two", gene);
+            }
+        }
+
+        #[test]
+        fn mu_comma_lambda() {
+            let population = evolve(Replacement::MuCommaLambda { mu: 2 });
+
+            for gene in genes(&population) {
+                assert!(gene == 3.0 || gene == 4.0, "{} doesn't come from the best two", gene);
+            }
+        }
+
+        #[test]
+        fn steady_state() {
+            let population = evolve(Replacement::SteadyState { replaced: 2 });
+
+            assert_eq!(
+                population[..3],
+                [individual(&[4.0, 4.0]), individual(&[3.0, 3.0]), individual(&[2.0, 2.0])]
+            );
+        }
+
+        #[test]
+        #[should_panic(expected = "mu must be at least 1")]
+        fn rejects_empty_mu() {
+            GeneticAlgorithm::new(
+                TournamentSelection::new(2),
+                UniformCrossover::new(),
+                GaussianMutation::new(0.0, 0.0),
+            )
+            .with_replacement(Replacement::MuCommaLambda { mu: 0 });
+        }
+
+        #[test]
+        #[should_panic(expected = "mu (6) cannot exceed the population's size (5)")]
+        fn rejects_mu_larger_than_population() {
+            evolve(Replacement::MuPlusLambda { mu: 6 });
+        }
+
+        #[test]
+        #[should_panic(expected = "cannot keep 6 elites out of 5 individuals")]
+        fn rejects_too_many_elites() {
+            evolve(Replacement::Elitism { elites: 6 });
+        }
+
+        #[test]
+        #[should_panic(expected = "cannot replace 6 out of 5 individuals")]
+        fn rejects_replacing_too_many() {
+            evolve(Replacement::SteadyState { replaced: 6 });
+        }
+    }
+
+    mod statistics {
+        use super::*;
+
+        fn stats(mut ga: GeneticAlgorithm<RankSelection>) -> Statistics {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let population = vec![individual(&[0.0]), individual(&[1.0]), individual(&[3.0])];
+
+            ga.evolve(&mut rng, &population).1
+        }
+
+        fn ga() -> GeneticAlgorithm<RankSelection> {
+            GeneticAlgorithm::new(RankSelection::new(), UniformCrossover::new(), GaussianMutation::new(0.0, 0.0))
+        }
+
+        #[test]
+        fn skips_diversity_by_default() {
+            assert_eq!(stats(ga()).diversity(), None);
+        }
+
+        #[test]
+        fn diversity() {
+            // (1 + 3 + 2) / 3
+            assert_eq!(stats(ga().with_diversity()).diversity(), Some(2.0));
+            assert_eq!(stats(ga().with_fitness_sharing(FitnessSharing::new(1.0))).diversity(), Some(2.0));
+        }
+    }
 }
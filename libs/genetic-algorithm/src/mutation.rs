@@ -21,7 +21,7 @@ pub struct GaussianMutation {
 
 impl GaussianMutation {
     pub fn new(chance: f32, coeff: f32) -> Self {
-        assert!(chance >= 0.0 && chance <= 1.0);
+        assert!((0.0..=1.0).contains(&chance));
 
         Self { chance, coeff }
     }
@@ -39,11 +39,52 @@ impl MutationMethod for GaussianMutation {
     }
 }
 
+/// Enables or disables genes by flipping their mask bits; chromosomes
+/// without a mask are left untouched.
+#[derive(Clone, Debug)]
+pub struct MaskFlipMutation {
+    /// Probability of flipping a mask bit:
+    /// - 0.0 = no bits will be touched
+    /// - 1.0 = all bits will be flipped
+    chance: f32,
+}
+
+impl MaskFlipMutation {
+    pub fn new(chance: f32) -> Self {
+        assert!((0.0..=1.0).contains(&chance));
+
+        Self { chance }
+    }
+}
+
+impl MutationMethod for MaskFlipMutation {
+    fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) {
+        if let Some(mask) = child.mask_mut() {
+            for bit in mask {
+                if rng.gen_bool(self.chance as _) {
+                    *bit = !*bit;
+                }
+            }
+        }
+    }
+}
+
+/// Applies both mutations, one after another - e.g.
+/// `(GaussianMutation::new(..), MaskFlipMutation::new(..))`.
+impl<A, B> MutationMethod for (A, B)
+where
+    A: MutationMethod,
+    B: MutationMethod,
+{
+    fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) {
+        self.0.mutate(rng, child);
+        self.1.mutate(rng, child);
+    }
+}
+
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-
     use super::*;
     use rand::SeedableRng;
     use rand_chacha::ChaCha8Rng;
@@ -62,7 +103,6 @@ mod tests {
     }
 
     mod given_zero_chance {
-        use crate::mutation::GaussianMutation;
         mod and_zero_coefficient {
             use crate::mutation::tests::get_actual;
 
@@ -155,4 +195,55 @@ mod tests {
             }
         }
     }
-}
\ No newline at end of file
+
+    mod mask_flip_mutation {
+        use super::*;
+
+        fn chromosome() -> Chromosome {
+            let chromosome: Chromosome = vec![1.0, 2.0, 3.0, 4.0].into_iter().collect();
+            chromosome.with_mask(vec![true, true, false, false])
+        }
+
+        #[test]
+        fn given_zero_chance_does_not_flip_bits() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut child = chromosome();
+
+            MaskFlipMutation::new(0.0).mutate(&mut rng, &mut child);
+
+            assert_eq!(child, chromosome());
+        }
+
+        #[test]
+        fn given_max_chance_flips_all_bits_but_keeps_genes() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut child = chromosome();
+
+            MaskFlipMutation::new(1.0).mutate(&mut rng, &mut child);
+
+            assert_eq!(child.mask(), Some(&[false, false, true, true][..]));
+            assert_eq!(child.into_iter().collect::<Vec<_>>(), vec![1.0, 2.0, 3.0, 4.0]);
+        }
+
+        #[test]
+        fn ignores_chromosomes_without_mask() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut child: Chromosome = vec![1.0, 2.0].into_iter().collect();
+
+            MaskFlipMutation::new(1.0).mutate(&mut rng, &mut child);
+
+            assert_eq!(child.mask(), None);
+        }
+
+        #[test]
+        fn composes_with_gaussian_mutation() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut child = chromosome();
+
+            (GaussianMutation::new(1.0, 0.5), MaskFlipMutation::new(1.0)).mutate(&mut rng, &mut child);
+
+            assert_eq!(child.mask(), Some(&[false, false, true, true][..]));
+            assert!(child.iter().zip(chromosome().iter()).all(|(a, b)| a != b));
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use crate::*;
+
+/// Fitness sharing - individuals crowded together have to share their
+/// niche's fitness, which keeps the population from converging on a single
+/// solution:
+///
+///   shared(i) = fitness(i) / sum_j sh(distance(i, j))
+///   sh(d) = 1 - (d / radius)^alpha for d < radius, 0 otherwise
+///
+/// Every individual is its own neighbour, so the divisor is at least one;
+/// fitness is expected to be non-negative.
+#[derive(Clone, Debug)]
+pub struct FitnessSharing {
+    radius: f32,
+    alpha: f32,
+}
+
+impl FitnessSharing {
+    /// Creates fitness sharing with given niche radius (in terms of
+    /// `Individual::distance()`) and the usual, linear `alpha = 1`.
+    pub fn new(radius: f32) -> Self {
+        assert!(radius > 0.0);
+
+        Self { radius, alpha: 1.0 }
+    }
+
+    pub fn with_alpha(mut self, alpha: f32) -> Self {
+        assert!(alpha > 0.0);
+
+        self.alpha = alpha;
+        self
+    }
+
+    /// Computes shared fitness of each individual, in the population's
+    /// order.
+    pub fn shared_fitness<I>(&self, population: &[I]) -> Vec<f32>
+    where
+        I: Individual,
+    {
+        let mut niches = vec![0.0; population.len()];
+
+        for (i, a) in population.iter().enumerate() {
+            niches[i] += 1.0;
+
+            for (j, b) in population.iter().enumerate().skip(i + 1) {
+                let distance = a.distance(b);
+
+                if distance < self.radius {
+                    let share = 1.0 - (distance / self.radius).powf(self.alpha);
+
+                    niches[i] += share;
+                    niches[j] += share;
+                }
+            }
+        }
+
+        population
+            .iter()
+            .zip(niches)
+            .map(|(individual, niche)| individual.fitness() / niche)
+            .collect()
+    }
+
+    pub(crate) fn apply<I>(&self, population: &[I]) -> Vec<Shared<I>>
+    where
+        I: Individual + Clone,
+    {
+        population
+            .iter()
+            .cloned()
+            .zip(self.shared_fitness(population))
+            .map(|(individual, fitness)| Shared { individual, fitness })
+            .collect()
+    }
+}
+
+/// Individual that pretends to have its shared fitness.
+#[derive(Clone, Debug)]
+pub(crate) struct Shared<I> {
+    individual: I,
+    fitness: f32,
+}
+
+impl<I> Individual for Shared<I>
+where
+    I: Individual,
+{
+    fn fitness(&self) -> f32 {
+        self.fitness
+    }
+
+    fn chromosome(&self) -> &Chromosome {
+        self.individual.chromosome()
+    }
+
+    fn create(chromosome: Chromosome) -> Self {
+        Self {
+            individual: I::create(chromosome),
+            fitness: 0.0,
+        }
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        self.individual.distance(&other.individual)
+    }
+}
+
+/// Average distance between two individuals of the population.
+pub(crate) fn diversity<I>(population: &[I]) -> f32
+where
+    I: Individual,
+{
+    let mut sum = 0.0;
+    let mut pairs = 0;
+
+    for (i, a) in population.iter().enumerate() {
+        for b in &population[i + 1..] {
+            sum += a.distance(b);
+            pairs += 1;
+        }
+    }
+
+    if pairs == 0 {
+        0.0
+    } else {
+        sum / pairs as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::individual::TestIndividual;
+
+    fn individual(genes: &[f32]) -> TestIndividual {
+        TestIndividual::create(genes.iter().cloned().collect())
+    }
+
+    mod shared_fitness {
+        use super::*;
+
+        #[test]
+        fn test() {
+            // Fitness is the sum of genes: 1, 1, 5 and 6
+            let population = vec![
+                individual(&[1.0]),
+                individual(&[1.0]),
+                individual(&[5.0]),
+                individual(&[6.0]),
+            ];
+
+            let actual = FitnessSharing::new(2.0).shared_fitness(&population);
+
+            // The first two share a niche in full, while the other two are
+            // half-way within each other's
+            let expected = [0.5, 0.5, 5.0 / 1.5, 6.0 / 1.5];
+
+            approx::assert_relative_eq!(actual.as_slice(), expected.as_ref());
+        }
+
+        #[test]
+        fn alpha() {
+            let population = vec![individual(&[1.0]), individual(&[2.0])];
+            let actual = FitnessSharing::new(2.0).with_alpha(2.0).shared_fitness(&population);
+
+            // sh(1.0) = 1 - 0.5^2
+            let expected = [1.0 / 1.75, 2.0 / 1.75];
+
+            approx::assert_relative_eq!(actual.as_slice(), expected.as_ref());
+        }
+    }
+
+    mod diversity {
+        use super::*;
+
+        #[test]
+        fn test() {
+            let population = vec![
+                individual(&[0.0, 0.0]),
+                individual(&[3.0, 4.0]),
+                individual(&[0.0, 0.0]),
+            ];
+
+            approx::assert_relative_eq!(diversity(&population), 10.0 / 3.0);
+        }
+
+        #[test]
+        fn single_individual() {
+            approx::assert_relative_eq!(diversity(&[individual(&[1.0])]), 0.0);
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use crate::*;
+
+/// How `GeneticAlgorithm::evolve()` puts the next generation together; the
+/// population's size stays the same either way.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Replacement {
+    /// Children replace the whole population
+    #[default]
+    Generational,
+
+    /// `elites` best individuals are carried over unchanged, the rest get
+    /// replaced with children
+    Elitism { elites: usize },
+
+    /// (mu+lambda) - `mu` best individuals survive and become the only
+    /// parents of the remaining `lambda = population - mu` individuals;
+    /// since children get evaluated along with the survivors, the next
+    /// generation's survivors are the best of parents and children together
+    MuPlusLambda { mu: usize },
+
+    /// (mu,lambda) - `mu` best individuals become the only parents, but
+    /// don't survive themselves: the whole population gets replaced with
+    /// their children
+    MuCommaLambda { mu: usize },
+
+    /// Only `replaced` worst individuals get replaced with children (bred
+    /// from the whole population), everyone else carries on
+    SteadyState { replaced: usize },
+}
+
+impl Replacement {
+    /// Panics if the parameters make no sense regardless of the population's
+    /// size, i.e. if there'd be no parents at all.
+    pub(crate) fn validate(self) {
+        if let Self::MuPlusLambda { mu } | Self::MuCommaLambda { mu } = self {
+            assert!(mu > 0, "mu must be at least 1");
+        }
+    }
+
+    /// Panics if the parameters don't fit a population of given size.
+    pub(crate) fn validate_for(self, population: usize) {
+        match self {
+            Self::Generational => {}
+
+            Self::Elitism { elites } => assert!(
+                elites <= population,
+                "cannot keep {} elites out of {} individuals",
+                elites,
+                population
+            ),
+
+            Self::MuPlusLambda { mu } | Self::MuCommaLambda { mu } => assert!(
+                mu <= population,
+                "mu ({}) cannot exceed the population's size ({})",
+                mu,
+                population
+            ),
+
+            Self::SteadyState { replaced } => assert!(
+                replaced <= population,
+                "cannot replace {} out of {} individuals",
+                replaced,
+                population
+            ),
+        }
+    }
+
+    // Returns how many of the best individuals survive, how many of them
+    // (if not everyone) can become parents, and how many children to breed;
+    // expects the replacement to be valid for the population's size
+    pub(crate) fn plan(self, population: usize) -> (usize, Option<usize>, usize) {
+        match self {
+            Self::Generational => (0, None, population),
+            Self::Elitism { elites } => (elites, None, population - elites),
+            Self::MuPlusLambda { mu } => (mu, Some(mu), population - mu),
+            Self::MuCommaLambda { mu } => (0, Some(mu), population),
+            Self::SteadyState { replaced } => (population - replaced, None, replaced),
+        }
+    }
+}
+
+/// Indices of the population's individuals, best first (NaN fitness comes
+/// last).
+pub(crate) fn ranking<I>(population: &[I]) -> Vec<usize>
+where
+    I: Individual,
+{
+    let fitness = |idx: usize| {
+        let fitness = population[idx].fitness();
+
+        if fitness.is_nan() {
+            f32::NEG_INFINITY
+        } else {
+            fitness
+        }
+    };
+
+    let mut ranking: Vec<usize> = (0..population.len()).collect();
+
+    ranking.sort_by(|&a, &b| fitness(b).total_cmp(&fitness(a)));
+    ranking
+}
@@ -1,21 +1,141 @@
 
-use rand::{seq::SliceRandom, RngCore};
+use std::ops::AddAssign;
+
+use rand::distributions::uniform::{SampleUniform, Uniform};
+use rand::{distributions::Distribution, seq::SliceRandom, Rng, RngCore};
 
 use crate::*;
 
+/// Strategy of picking parents for the next generation.
+///
+/// Selection methods are stateless: once per generation, a method gets
+/// `prepare()`-d from the (immutable) population, doing whatever
+/// precomputation it needs - e.g. ranking the individuals - and the
+/// returned `PreparedSelector` then samples as many parents as necessary.
+/// Both only ever need `&self`, so they can be shared between threads.
 pub trait SelectionMethod {
-    fn select<'a, I>(&mut self, rng: &mut dyn RngCore, population: &'a[I]) -> &'a I
+    type Selector<'a, I>: PreparedSelector<'a, I>
     where
-        I: Individual;
+        I: Individual + 'a;
 
-    fn sort<'a, I>(&mut self, population: &mut [I]) -> &Self
+    /// Prepares a selector for given population (which mustn't be empty);
+    /// `generation` is the number of generations evolved so far, for methods
+    /// that change over time.
+    fn prepare<'a, I>(&self, population: &'a [I], generation: usize) -> Self::Selector<'a, I>
     where
         I: Individual;
-    fn set_sorted_population(&mut self) -> &Self;
-    fn set_not_sorted_population(&mut self) -> &Self;
 }
 
+/// Samples individuals from the population it's been prepared for, see
+/// `SelectionMethod::prepare()`.
+pub trait PreparedSelector<'a, I> {
+    fn select(&self, rng: &mut dyn RngCore) -> &'a I;
+
+    /// Selects `count` individuals at once; by default it's just `select()`
+    /// called `count` times.
+    fn select_many(&self, rng: &mut dyn RngCore, count: usize) -> Vec<&'a I> {
+        (0..count).map(|_| self.select(rng)).collect()
+    }
+
+    /// Whether `select_many()` picks individuals jointly rather than one by
+    /// one - if so, `GeneticAlgorithm` selects all parents of the next
+    /// generation up front; otherwise it selects two of them right before
+    /// breeding each child.
+    fn selects_jointly(&self) -> bool {
+        false
+    }
+}
+
+/// Selector picking individuals with probabilities proportional to their
+/// weights - prepared by `RouletteWheelSelection`, `RankSelection` and
+/// `BoltzmannSelection`.
+///
+/// Weights are summed up and sampled as `W`, the same way `WeightedIndex`
+/// (and so `choose_weighted()`) does it - roulette spins its wheel in `f32`,
+/// so that seeded runs draw exactly the numbers they always have.
 #[derive(Clone, Debug)]
+pub struct WeightedSelector<'a, I, W = f64> {
+    population: &'a [I],
+
+    // Indices of the individuals `cumulative` refers to, if they're in a
+    // different order than in `population`
+    order: Option<Vec<usize>>,
+    cumulative: Vec<W>,
+}
+
+impl<'a, I, W> WeightedSelector<'a, I, W>
+where
+    W: SampleUniform + Into<f64> + AddAssign + PartialOrd + Copy + Default,
+{
+    fn new(population: &'a [I], order: Option<Vec<usize>>, weights: impl IntoIterator<Item = W>) -> Self {
+        assert!(!population.is_empty(), "got an empty population");
+
+        let mut total = W::default();
+
+        let cumulative: Vec<W> = weights
+            .into_iter()
+            .map(|weight| {
+                let value: f64 = weight.into();
+
+                assert!(value >= 0.0 && value.is_finite(), "got an invalid weight: {}", value);
+
+                total += weight;
+                total
+            })
+            .collect();
+
+        assert_eq!(cumulative.len(), population.len());
+        assert!(total > W::default(), "weights sum up to zero, there's nothing to select from");
+
+        Self { population, order, cumulative }
+    }
+
+    fn total(&self) -> W {
+        self.cumulative[self.cumulative.len() - 1]
+    }
+
+    // Individual whose section of the wheel contains given point
+    fn at(&self, point: W) -> &'a I {
+        // `min()` guards against rounding errors near the wheel's end
+        let idx = self
+            .cumulative
+            .partition_point(|&sum| sum <= point)
+            .min(self.cumulative.len() - 1);
+
+        &self.population[self.order.as_ref().map_or(idx, |order| order[idx])]
+    }
+}
+
+impl<'a, I, W> PreparedSelector<'a, I> for WeightedSelector<'a, I, W>
+where
+    W: SampleUniform + Into<f64> + AddAssign + PartialOrd + Copy + Default,
+{
+    fn select(&self, rng: &mut dyn RngCore) -> &'a I {
+        self.at(Uniform::new(W::default(), self.total()).sample(rng))
+    }
+}
+
+// Fitness values as weights of a wheel; when they're all zero (e.g. in the
+// simulation's first generation, if no bird has found any food), the wheel
+// would have no sections at all - so everyone gets an equal one instead
+fn fitness_wheel<I, W>(population: &[I], weight: impl Fn(f32) -> W) -> Vec<W>
+where
+    I: Individual,
+    W: Into<f64> + Copy,
+{
+    let weights: Vec<W> = population.iter().map(|individual| weight(individual.fitness())).collect();
+
+    if weights.iter().all(|&weight| weight.into() == 0.0) {
+        vec![weight(1.0); population.len()]
+    } else {
+        weights
+    }
+}
+
+/// Picks individuals with probability proportional to their fitness, which
+/// must not be negative; when it's all zero, everyone gets the same chance
+/// (rather than the selection panicking over an empty wheel).
+#[derive(Clone, Debug, Default)]
 pub struct RouletteWheelSelection;
 
 impl RouletteWheelSelection {
@@ -25,89 +145,368 @@ impl RouletteWheelSelection {
 }
 
 impl SelectionMethod for RouletteWheelSelection {
+    type Selector<'a, I> = WeightedSelector<'a, I, f32>
+    where
+        I: Individual + 'a;
+
+    fn prepare<'a, I>(&self, population: &'a [I], _generation: usize) -> Self::Selector<'a, I>
+    where
+        I: Individual,
+    {
+        WeightedSelector::new(population, None, fitness_wheel(population, |fitness| fitness))
+    }
+}
+
+/// Like `RouletteWheelSelection`, but spinning a wheel with `count`
+/// equally spaced pointers just once, instead of a single pointer `count`
+/// times - so that each individual gets selected either `floor` or `ceil`
+/// of its expected number of times, no matter how small the population is.
+///
+/// Fitness values must not be negative; when they're all zero, everyone
+/// gets the same chance.
+#[derive(Clone, Debug, Default)]
+pub struct StochasticUniversalSampling;
+
+impl StochasticUniversalSampling {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// Selector prepared by `StochasticUniversalSampling`.
+#[derive(Clone, Debug)]
+pub struct UniversalSampler<'a, I>(WeightedSelector<'a, I>);
+
+impl SelectionMethod for StochasticUniversalSampling {
+    type Selector<'a, I> = UniversalSampler<'a, I>
+    where
+        I: Individual + 'a;
 
-    fn select<'a, I>(&mut self, rng: &mut dyn RngCore, population: &'a[I]) -> &'a I
+    fn prepare<'a, I>(&self, population: &'a [I], _generation: usize) -> Self::Selector<'a, I>
     where
         I: Individual,
     {
-            population
-                .choose_weighted(rng, |individual| individual.fitness())
-                .expect("got an empty population")
+        UniversalSampler(WeightedSelector::new(
+            population,
+            None,
+            fitness_wheel(population, |fitness| fitness as f64),
+        ))
     }
-    fn sort<'a, I>(&mut self, population: &mut [I]) -> &Self
-        where
-            I: Individual {
-                self
+}
+
+impl<'a, I> PreparedSelector<'a, I> for UniversalSampler<'a, I> {
+    fn select(&self, rng: &mut dyn RngCore) -> &'a I {
+        self.0.select(rng)
     }
-    fn set_sorted_population(&mut self) -> &Self {
-        self
+
+    fn select_many(&self, rng: &mut dyn RngCore, count: usize) -> Vec<&'a I> {
+        if count == 0 {
+            return Vec::new();
+        }
+
+        let step = self.0.total() / count as f64;
+        let start = rng.gen_range(0.0..step);
+
+        let mut selected: Vec<&I> = (0..count)
+            .map(|idx| self.0.at(start + idx as f64 * step))
+            .collect();
+
+        // Pointers go around the wheel in order, so without shuffling the
+        // neighbouring parents would be similar to each other
+        selected.shuffle(rng);
+        selected
     }
-    fn set_not_sorted_population(&mut self) -> &Self {
-        self
+
+    fn selects_jointly(&self) -> bool {
+        true
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct RankSelection{
-    is_sorted: bool,
+/// How steeply selection chances grow with rank, see `RankSelection`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum RankPressure {
+    /// Weight equal to the rank itself - from 1 for the worst individual up
+    /// to `n` for the best one
+    #[default]
+    Proportional,
+
+    /// Linear ranking with pressure within <1, 2>: the best individual is
+    /// expected to be selected `pressure` times per `n` selections, the
+    /// worst one `2 - pressure` times (so never at 2.0, and just as often
+    /// as anyone else at 1.0)
+    Linear(f64),
+
+    /// Exponential ranking with base within (0, 1>: each individual is
+    /// `base` times less likely to be selected than the next better one
+    Exponential(f64),
+}
+
+impl RankPressure {
+    // Weight of given rank (counted from 1, for the worst individual) - a
+    // fractional one in case of ties
+    fn weight(self, rank: f64, len: usize) -> f64 {
+        let len = len as f64;
+
+        match self {
+            Self::Proportional => rank,
+            Self::Linear(_) if len <= 1.0 => 1.0,
+            Self::Linear(pressure) => (2.0 - pressure) + 2.0 * (pressure - 1.0) * (rank - 1.0) / (len - 1.0),
+            Self::Exponential(base) => base.powf(len - rank),
+        }
+    }
+}
+
+/// Selects individuals by their rank instead of the raw fitness, so only
+/// the order of fitness values matters - they can be negative, and a
+/// single outstanding individual doesn't take over the population.
+///
+/// Tied individuals share the average of their ranks, while NaN fitness
+/// counts as the worst one. Ranks get computed once per `prepare()`, after
+/// which every selection is a binary search.
+#[derive(Clone, Debug, Default)]
+pub struct RankSelection {
+    pressure: RankPressure,
 }
 
 impl RankSelection {
     pub fn new() -> Self {
-        Self {
-            is_sorted: false
+        Self::default()
+    }
+
+    pub fn with_pressure(mut self, pressure: RankPressure) -> Self {
+        match pressure {
+            RankPressure::Proportional => (),
+            RankPressure::Linear(pressure) => assert!((1.0..=2.0).contains(&pressure)),
+            RankPressure::Exponential(base) => assert!(base > 0.0 && base <= 1.0),
+        }
+
+        self.pressure = pressure;
+        self
+    }
+
+    // Returns indices of the population's individuals, from the worst to
+    // the best, along with their weights
+    fn rank<I>(&self, population: &[I]) -> (Vec<usize>, Vec<f64>)
+    where
+        I: Individual,
+    {
+        let mut order: Vec<usize> = (0..population.len()).collect();
+        order.sort_by(|&a, &b| fitness(&population[a]).total_cmp(&fitness(&population[b])));
+
+        let mut weights = Vec::with_capacity(order.len());
+        let mut start = 0;
+
+        while start < order.len() {
+            let key = fitness(&population[order[start]]);
+            let end = start
+                + order[start..]
+                    .iter()
+                    .take_while(|&&idx| fitness(&population[idx]) == key)
+                    .count();
+
+            // Ranks `start + 1 ..= end`, averaged
+            let weight = self.pressure.weight((start + 1 + end) as f64 / 2.0, order.len());
+
+            weights.resize(end, weight);
+            start = end;
         }
+
+        (order, weights)
     }
 }
 
 impl SelectionMethod for RankSelection {
+    type Selector<'a, I> = WeightedSelector<'a, I>
+    where
+        I: Individual + 'a;
+
+    fn prepare<'a, I>(&self, population: &'a [I], _generation: usize) -> Self::Selector<'a, I>
+    where
+        I: Individual,
+    {
+        let (order, weights) = self.rank(population);
+
+        WeightedSelector::new(population, Some(order), weights)
+    }
+}
+
+// NaN counts as the worst fitness there is
+fn fitness<I>(individual: &I) -> f32
+where
+    I: Individual,
+{
+    let fitness = individual.fitness();
+
+    if fitness.is_nan() {
+        f32::NEG_INFINITY
+    } else {
+        fitness
+    }
+}
+
+/// Picks the best of `size` individuals drawn at random (with
+/// replacement) - since only the order of fitness values matters, they can
+/// be negative too.
+///
+/// By default the best contestant always wins; with a `probability` below
+/// one, the tournament becomes probabilistic: the best contestant wins with
+/// `p`, the second best with `p * (1 - p)`, the third with `p * (1 - p)^2`
+/// and so on, with the worst one getting whatever's left.
+#[derive(Clone, Debug)]
+pub struct TournamentSelection {
+    size: usize,
+    probability: f64,
+}
+
+impl TournamentSelection {
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0);
+
+        Self { size, probability: 1.0 }
+    }
+
+    pub fn with_probability(mut self, probability: f64) -> Self {
+        assert!(probability > 0.0 && probability <= 1.0);
+
+        self.probability = probability;
+        self
+    }
+}
+
+/// Selector prepared by `TournamentSelection`.
+#[derive(Clone, Debug)]
+pub struct TournamentSelector<'a, I> {
+    tournament: TournamentSelection,
+    population: &'a [I],
+}
 
-    fn select<'a, I>(&mut self, rng: &mut dyn RngCore, population: &'a[I]) -> &'a I
+impl SelectionMethod for TournamentSelection {
+    type Selector<'a, I> = TournamentSelector<'a, I>
+    where
+        I: Individual + 'a;
+
+    fn prepare<'a, I>(&self, population: &'a [I], _generation: usize) -> Self::Selector<'a, I>
     where
         I: Individual,
-        {
-            // assuming used sort method to sort population before here
-            let total_fitness: f64 = (1..=population.len()).sum::<usize>() as f64; 
-            // Calculate the total fitness of the population as the sum of all ranks
-            // Choose an individual from the population using weighted random selection,
-            // where the weight of each individual is its rank divided by the total fitness of the population
-            population
-                .choose_weighted(rng, |individual| {
-                    let rank = population
-                        .iter()
-                        .position(|x| x.fitness() == individual.fitness())
-                        .unwrap() + 1; // Find the rank of the individual by finding its position in the sorted population and adding 1
-                    // The + 1 is added to the result of the position method to convert the zero-based index returned by the method into a one-based rank. ranks are typically assigned starting from 1
-                    rank as f64 / total_fitness // Calculate the selection probability of the individual as its rank divided by the total fitness of the population
-                })
-                .expect("got an empty population")
-        }
-
-        fn sort<'a, I>(&mut self, population: &mut [I]) -> &Self
-        where
-                I: Individual,
-            {
-                if !self.is_sorted {
-                    population.sort_by(|a, b| a.fitness().partial_cmp(&b.fitness()).unwrap()); // Sort the population by fitness in ascending order
-                    self.is_sorted = true; // Set is_sorted to true
+    {
+        assert!(!population.is_empty(), "got an empty population");
+
+        TournamentSelector { tournament: self.clone(), population }
+    }
+}
+
+impl<'a, I> PreparedSelector<'a, I> for TournamentSelector<'a, I>
+where
+    I: Individual,
+{
+    fn select(&self, rng: &mut dyn RngCore) -> &'a I {
+        let TournamentSelection { size, probability } = self.tournament;
+        let population = self.population;
+
+        // NaN never wins, unless there's nothing else to choose from (see
+        // `fitness()`)
+        let mut contestants: Vec<&I> = (0..size)
+            .map(|_| &population[rng.gen_range(0..population.len())])
+            .collect();
+
+        contestants.sort_by(|&a, &b| fitness(b).total_cmp(&fitness(a)));
+
+        if probability < 1.0 {
+            for &contestant in &contestants[..size - 1] {
+                if rng.gen_bool(probability) {
+                    return contestant;
                 }
-                self.set_sorted_population();
-                self
             }
-        fn set_sorted_population(&mut self) -> &Self {
-            self.is_sorted = true;
-            self
+
+            return contestants[size - 1];
         }
-        fn set_not_sorted_population(&mut self) -> &Self {
-            self.is_sorted = false;
-            self
+
+        contestants[0]
+    }
+}
+
+/// Picks individuals with probability proportional to
+/// `exp(fitness / temperature)`, where the temperature starts at
+/// `initial_temperature` and gets multiplied by `cooling` every generation
+/// (down to `min_temperature`).
+///
+/// While it's hot, the selection is close to uniform and the population
+/// explores; as it cools down, the best individuals take over. Only
+/// differences between fitness values matter, so they can be negative too
+/// - but they must be finite.
+#[derive(Clone, Debug)]
+pub struct BoltzmannSelection {
+    initial_temperature: f32,
+    cooling: f32,
+    min_temperature: f32,
+}
+
+impl BoltzmannSelection {
+    pub fn new(initial_temperature: f32) -> Self {
+        assert!(initial_temperature > 0.0);
+
+        Self {
+            initial_temperature,
+            cooling: 0.95,
+            min_temperature: 0.01,
         }
+    }
+
+    pub fn with_cooling(mut self, cooling: f32) -> Self {
+        assert!(cooling > 0.0 && cooling <= 1.0);
+
+        self.cooling = cooling;
+        self
+    }
+
+    pub fn with_min_temperature(mut self, min_temperature: f32) -> Self {
+        assert!(min_temperature > 0.0);
+
+        self.min_temperature = min_temperature;
+        self
+    }
+
+    /// Temperature for given generation.
+    pub fn temperature(&self, generation: usize) -> f32 {
+        let temperature = self.initial_temperature * self.cooling.powi(generation.min(i32::MAX as usize) as i32);
+
+        temperature.max(self.min_temperature)
+    }
+}
+
+impl SelectionMethod for BoltzmannSelection {
+    type Selector<'a, I> = WeightedSelector<'a, I>
+    where
+        I: Individual + 'a;
+
+    fn prepare<'a, I>(&self, population: &'a [I], generation: usize) -> Self::Selector<'a, I>
+    where
+        I: Individual,
+    {
+        let temperature = self.temperature(generation) as f64;
+
+        let fitness: Vec<f64> = population
+            .iter()
+            .map(|individual| {
+                let fitness = individual.fitness();
+
+                assert!(fitness.is_finite(), "BoltzmannSelection needs finite fitness, got {}", fitness);
+
+                fitness as f64
+            })
+            .collect();
+
+        // Relative to the best individual (softmax-style), so that `exp()`
+        // stays within <0, 1> and can't overflow
+        let max = fitness.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let weights = fitness.into_iter().map(|fitness| ((fitness - max) / temperature).exp());
+
+        WeightedSelector::new(population, None, weights)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-
     mod roulette_wheel_selection {
 
         mod test_selection_with_histogram_nondeterminstic {
@@ -116,27 +515,25 @@ mod tests {
             use rand::SeedableRng;
             use rand_chacha::ChaCha8Rng;
 
-            use crate::{selection::{RouletteWheelSelection, SelectionMethod}, individual::{TestIndividual, Individual}};
+            use crate::{selection::{PreparedSelector, RouletteWheelSelection, SelectionMethod}, individual::{TestIndividual, Individual}};
 
             #[test]
             fn test() {
-                let mut method: RouletteWheelSelection = RouletteWheelSelection::new();
-                method.set_not_sorted_population();
+                let method = RouletteWheelSelection::new();
                 let mut rng = ChaCha8Rng::from_seed(Default::default());
 
-                let mut population = vec![
+                let population = vec![
                     TestIndividual::new(2.0),
                     TestIndividual::new(1.0),
                     TestIndividual::new(4.0),
                     TestIndividual::new(3.0),
                 ];
 
+                let selector = method.prepare(&population, 0);
                 let mut actual_histogram = BTreeMap::new();
 
-
                 for _ in 0..1000 {
-                    method.sort(&mut population);
-                    let fitness = method.select(&mut rng, &population)
+                    let fitness = selector.select(&mut rng)
                         .fitness() as i32;
 
                     *actual_histogram
@@ -155,6 +552,25 @@ mod tests {
                 assert_eq!(actual_histogram, expected_histogram);
             }
         }
+
+        mod zero_fitness {
+            use rand::SeedableRng;
+            use rand_chacha::ChaCha8Rng;
+
+            use crate::{selection::{PreparedSelector, RouletteWheelSelection, SelectionMethod}, individual::TestIndividual};
+
+            #[test]
+            fn gives_everyone_the_same_chance() {
+                let population = vec![TestIndividual::new(0.0), TestIndividual::new(0.0)];
+                let selector = RouletteWheelSelection::new().prepare(&population, 0);
+                let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+                let selected = selector.select_many(&mut rng, 100);
+
+                assert!(selected.iter().any(|&individual| std::ptr::eq(individual, &population[0])));
+                assert!(selected.iter().any(|&individual| std::ptr::eq(individual, &population[1])));
+            }
+        }
     }
 
     mod rank_selection {
@@ -165,26 +581,25 @@ mod tests {
             use rand::SeedableRng;
             use rand_chacha::ChaCha8Rng;
 
-            use crate::{selection::{SelectionMethod, RankSelection}, individual::{TestIndividual, Individual}};
+            use crate::{selection::{PreparedSelector, RankSelection, SelectionMethod}, individual::{TestIndividual, Individual}};
 
             #[test]
             fn test() {
-                let mut method: RankSelection = RankSelection::new();
-                method.set_not_sorted_population();
+                let method = RankSelection::new();
                 let mut rng = ChaCha8Rng::from_seed(Default::default());
 
-                let mut population = vec![
+                let population = vec![
                     TestIndividual::new(2.0),
                     TestIndividual::new(1.0),
                     TestIndividual::new(4.0),
                     TestIndividual::new(3.0),
                 ];
 
+                let selector = method.prepare(&population, 0);
                 let mut actual_histogram = BTreeMap::new();
 
                 for _ in 0..1000 {
-                    method.sort(&mut population);
-                    let fitness = method.select(&mut rng, &population)
+                    let fitness = selector.select(&mut rng)
                         .fitness() as i32;
 
                     *actual_histogram
@@ -204,5 +619,379 @@ mod tests {
             }
         }
     }
-    
+
+    mod rank_pressure {
+        use std::collections::BTreeMap;
+
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use crate::{selection::{PreparedSelector, RankPressure, RankSelection, SelectionMethod}, individual::{TestIndividual, Individual}};
+
+        fn histogram(method: RankSelection, population: &[TestIndividual]) -> BTreeMap<i32, i32> {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut histogram = BTreeMap::new();
+
+            for individual in method.prepare(population, 0).select_many(&mut rng, 1000) {
+                *histogram.entry(individual.fitness() as i32).or_insert(0) += 1;
+            }
+
+            histogram
+        }
+
+        fn population() -> Vec<TestIndividual> {
+            vec![
+                TestIndividual::new(-2.0),
+                TestIndividual::new(-1.0),
+                TestIndividual::new(-4.0),
+                TestIndividual::new(-3.0),
+            ]
+        }
+
+        #[test]
+        fn linear() {
+            let method = RankSelection::new().with_pressure(RankPressure::Linear(2.0));
+            let actual_histogram = histogram(method, &population());
+
+            // The worst individual's weight is zero
+            let expected_histogram = BTreeMap::from_iter(vec![
+                // (fitness, how many times this fitness has been chosen)
+                (-3, 153),
+                (-2, 332),
+                (-1, 515),
+            ]);
+
+            assert_eq!(actual_histogram, expected_histogram);
+        }
+
+        #[test]
+        fn exponential() {
+            let method = RankSelection::new().with_pressure(RankPressure::Exponential(0.5));
+            let actual_histogram = histogram(method, &population());
+
+            let expected_histogram = BTreeMap::from_iter(vec![
+                // (fitness, how many times this fitness has been chosen)
+                (-4, 74),
+                (-3, 117),
+                (-2, 265),
+                (-1, 544),
+            ]);
+
+            assert_eq!(actual_histogram, expected_histogram);
+        }
+
+        #[test]
+        fn ties_share_average_rank() {
+            // Ranks 1.5, 1.5 and 3, so the weights still sum up to 6
+            let population = vec![TestIndividual::new(1.0), TestIndividual::new(3.0), TestIndividual::new(1.0)];
+            let (order, weights) = RankSelection::new().rank(&population);
+
+            assert_eq!(order, [0, 2, 1]);
+            assert_eq!(weights, [1.5, 1.5, 3.0]);
+        }
+
+        #[test]
+        fn nan_is_the_worst() {
+            let population = vec![
+                TestIndividual::new(1.0),
+                TestIndividual::new(f32::NAN),
+                TestIndividual::new(-1.0),
+            ];
+
+            let (order, _) = RankSelection::new().rank(&population);
+
+            assert_eq!(order, [1, 2, 0]);
+        }
+    }
+
+    mod prepared_selector {
+        use std::thread;
+
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use crate::{selection::{PreparedSelector, RankSelection, SelectionMethod}, individual::{TestIndividual, Individual}};
+
+        #[test]
+        fn can_be_shared_between_threads() {
+            let population: Vec<_> = (0..10).map(|fitness| TestIndividual::new(fitness as f32)).collect();
+            let selector = RankSelection::new().prepare(&population, 0);
+
+            let select = || {
+                let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+                selector
+                    .select_many(&mut rng, 100)
+                    .into_iter()
+                    .map(|individual| individual.fitness())
+                    .collect::<Vec<_>>()
+            };
+
+            let (a, b) = thread::scope(|scope| {
+                let a = scope.spawn(select);
+                let b = scope.spawn(select);
+
+                (a.join().unwrap(), b.join().unwrap())
+            });
+
+            assert_eq!(a, b);
+        }
+    }
+
+    mod tournament_selection {
+        use std::collections::BTreeMap;
+
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use crate::{selection::{PreparedSelector, SelectionMethod, TournamentSelection}, individual::{TestIndividual, Individual}};
+
+        fn histogram(method: TournamentSelection, population: &[TestIndividual]) -> BTreeMap<i32, i32> {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let selector = method.prepare(population, 0);
+            let mut histogram = BTreeMap::new();
+
+            for _ in 0..1000 {
+                let fitness = selector.select(&mut rng).fitness() as i32;
+
+                *histogram.entry(fitness).or_insert(0) += 1;
+            }
+
+            histogram
+        }
+
+        fn population() -> Vec<TestIndividual> {
+            vec![
+                TestIndividual::new(2.0),
+                TestIndividual::new(1.0),
+                TestIndividual::new(4.0),
+                TestIndividual::new(3.0),
+            ]
+        }
+
+        #[test]
+        fn deterministic() {
+            let actual_histogram = histogram(TournamentSelection::new(2), &population());
+
+            let expected_histogram = BTreeMap::from_iter(vec![
+                // (fitness, how many times this fitness has been chosen)
+                (1, 75),
+                (2, 177),
+                (3, 300),
+                (4, 448),
+            ]);
+
+            assert_eq!(actual_histogram, expected_histogram);
+        }
+
+        #[test]
+        fn probabilistic() {
+            let actual_histogram = histogram(TournamentSelection::new(2).with_probability(0.75), &population());
+
+            let expected_histogram = BTreeMap::from_iter(vec![
+                // (fitness, how many times this fitness has been chosen)
+                (1, 166),
+                (2, 209),
+                (3, 267),
+                (4, 358),
+            ]);
+
+            assert_eq!(actual_histogram, expected_histogram);
+        }
+
+        #[test]
+        fn negative_fitness() {
+            let population = vec![
+                TestIndividual::new(-2.0),
+                TestIndividual::new(-1.0),
+                TestIndividual::new(-4.0),
+                TestIndividual::new(-3.0),
+            ];
+
+            let actual_histogram = histogram(TournamentSelection::new(2), &population);
+
+            let expected_histogram = BTreeMap::from_iter(vec![
+                // (fitness, how many times this fitness has been chosen)
+                (-4, 59),
+                (-3, 177),
+                (-2, 312),
+                (-1, 452),
+            ]);
+
+            assert_eq!(actual_histogram, expected_histogram);
+        }
+
+        #[test]
+        fn whole_population_tournament_picks_the_best() {
+            let actual_histogram = histogram(TournamentSelection::new(64), &population());
+            let expected_histogram = BTreeMap::from_iter(vec![(4, 1000)]);
+
+            assert_eq!(actual_histogram, expected_histogram);
+        }
+
+        #[test]
+        fn ignores_nan() {
+            let population = vec![TestIndividual::new(f32::NAN), TestIndividual::new(-1.0)];
+            let actual_histogram = histogram(TournamentSelection::new(4), &population);
+
+            // -1 is missing from a tournament only if all four contestants
+            // happen to be NaN
+            assert!(actual_histogram[&-1] > 900, "{:?}", actual_histogram);
+        }
+    }
+
+    mod stochastic_universal_sampling {
+        use std::collections::BTreeMap;
+
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use crate::{selection::{PreparedSelector, SelectionMethod, StochasticUniversalSampling}, individual::{TestIndividual, Individual}};
+
+        fn histogram(population: &[TestIndividual], count: usize) -> BTreeMap<i32, i32> {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut histogram = BTreeMap::new();
+
+            for individual in StochasticUniversalSampling::new().prepare(population, 0).select_many(&mut rng, count) {
+                *histogram.entry(individual.fitness() as i32).or_insert(0) += 1;
+            }
+
+            histogram
+        }
+
+        #[test]
+        fn test() {
+            let population = vec![
+                TestIndividual::new(2.0),
+                TestIndividual::new(1.0),
+                TestIndividual::new(4.0),
+                TestIndividual::new(3.0),
+            ];
+
+            // Each individual gets exactly its share
+            let actual_histogram = histogram(&population, 10);
+            let expected_histogram = BTreeMap::from_iter(vec![(1, 1), (2, 2), (3, 3), (4, 4)]);
+
+            assert_eq!(actual_histogram, expected_histogram);
+        }
+
+        #[test]
+        fn uneven_shares() {
+            let population = vec![TestIndividual::new(1.0), TestIndividual::new(2.0)];
+
+            // 4/3 and 8/3 get rounded one way or another
+            let actual_histogram = histogram(&population, 4);
+            let expected_histogram = BTreeMap::from_iter(vec![(1, 1), (2, 3)]);
+
+            assert_eq!(actual_histogram, expected_histogram);
+        }
+
+        #[test]
+        fn zero_fitness() {
+            let population = vec![TestIndividual::new(0.0), TestIndividual::new(0.0)];
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let selected = StochasticUniversalSampling::new().prepare(&population, 0).select_many(&mut rng, 6);
+
+            assert_eq!(selected.len(), 6);
+        }
+
+        #[test]
+        #[should_panic]
+        fn rejects_negative_fitness() {
+            histogram(&[TestIndividual::new(-1.0)], 1);
+        }
+    }
+
+    mod boltzmann_selection {
+        use std::collections::BTreeMap;
+
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use crate::{selection::{BoltzmannSelection, PreparedSelector, SelectionMethod}, individual::{TestIndividual, Individual}};
+
+        fn histogram(method: BoltzmannSelection, population: &[TestIndividual], generation: usize) -> BTreeMap<i32, i32> {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut histogram = BTreeMap::new();
+
+            for individual in method.prepare(population, generation).select_many(&mut rng, 1000) {
+                *histogram.entry(individual.fitness() as i32).or_insert(0) += 1;
+            }
+
+            histogram
+        }
+
+        fn population() -> Vec<TestIndividual> {
+            vec![
+                TestIndividual::new(-2.0),
+                TestIndividual::new(-1.0),
+                TestIndividual::new(-4.0),
+                TestIndividual::new(-3.0),
+            ]
+        }
+
+        #[test]
+        fn hot() {
+            let actual_histogram = histogram(BoltzmannSelection::new(10.0), &population(), 0);
+
+            let expected_histogram = BTreeMap::from_iter(vec![
+                // (fitness, how many times this fitness has been chosen)
+                (-4, 238),
+                (-3, 222),
+                (-2, 246),
+                (-1, 294),
+            ]);
+
+            assert_eq!(actual_histogram, expected_histogram);
+        }
+
+        #[test]
+        fn cooled_down() {
+            let method = BoltzmannSelection::new(10.0).with_cooling(0.5);
+
+            approx::assert_relative_eq!(method.temperature(4), 0.625);
+
+            let actual_histogram = histogram(method, &population(), 4);
+
+            let expected_histogram = BTreeMap::from_iter(vec![
+                // (fitness, how many times this fitness has been chosen)
+                (-4, 7),
+                (-3, 30),
+                (-2, 152),
+                (-1, 811),
+            ]);
+
+            assert_eq!(actual_histogram, expected_histogram);
+        }
+
+        #[test]
+        fn min_temperature() {
+            let method = BoltzmannSelection::new(1.0).with_cooling(0.1).with_min_temperature(0.05);
+
+            approx::assert_relative_eq!(method.temperature(0), 1.0);
+            approx::assert_relative_eq!(method.temperature(1), 0.1);
+            approx::assert_relative_eq!(method.temperature(1000), 0.05);
+        }
+
+        #[test]
+        fn huge_fitness() {
+            // exp(f32::MAX) alone would overflow
+            let population = vec![TestIndividual::new(f32::MAX), TestIndividual::new(0.0)];
+            let actual_histogram = histogram(BoltzmannSelection::new(1.0), &population, 0);
+
+            assert_eq!(actual_histogram, BTreeMap::from_iter(vec![(f32::MAX as i32, 1000)]));
+        }
+
+        #[test]
+        #[should_panic(expected = "BoltzmannSelection needs finite fitness, got inf")]
+        fn rejects_infinite_fitness() {
+            histogram(BoltzmannSelection::new(1.0), &[TestIndividual::new(f32::INFINITY), TestIndividual::new(1.0)], 0);
+        }
+
+        #[test]
+        #[should_panic(expected = "BoltzmannSelection needs finite fitness, got NaN")]
+        fn rejects_nan() {
+            histogram(BoltzmannSelection::new(1.0), &[TestIndividual::new(f32::NAN), TestIndividual::new(1.0)], 0);
+        }
+    }
 }
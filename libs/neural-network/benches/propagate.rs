@@ -0,0 +1,141 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use lib_neural_network::{Activation, LayerTopology, Network, Scratch};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+// (eye cells, hidden neurons) - the first one is what `Brain` uses by default
+const TOPOLOGIES: [(usize, usize); 3] = [(9, 18), (20, 40), (64, 128)];
+
+// Number of input vectors propagated at once by the `_batch` benchmarks
+const BATCH: usize = 64;
+
+fn network(inputs: usize, hidden: usize) -> Network {
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+    Network::random(
+        &mut rng,
+        &[
+            LayerTopology::new(inputs),
+            LayerTopology::new(hidden),
+            LayerTopology::new(2).with_activation(Activation::Tanh),
+        ],
+    )
+}
+
+/// The network's layout before `Layer` got its contiguous weight matrix -
+/// every neuron owns its weights and every layer allocates its output;
+/// benchmarked as `baseline`, next to the current layout.
+struct Baseline {
+    layers: Vec<(Vec<Neuron>, Activation)>,
+}
+
+struct Neuron {
+    bias: f32,
+    weights: Vec<f32>,
+}
+
+impl Baseline {
+    fn new(network: &Network) -> Self {
+        let topology = network.topology();
+        let mut weights = network.weights().into_iter();
+
+        let layers = topology
+            .windows(2)
+            .map(|layers| {
+                let neurons = (0..layers[1].neurons)
+                    .map(|_| Neuron {
+                        bias: weights.next().unwrap(),
+                        weights: weights.by_ref().take(layers[0].neurons).collect(),
+                    })
+                    .collect();
+
+                (neurons, layers[1].activation.clone())
+            })
+            .collect();
+
+        Self { layers }
+    }
+
+    fn propagate(&self, inputs: Vec<f32>) -> Vec<f32> {
+        self.layers.iter().fold(inputs, |inputs, (neurons, activation)| {
+            neurons
+                .iter()
+                .map(|neuron| {
+                    let output = inputs
+                        .iter()
+                        .zip(&neuron.weights)
+                        .map(|(input, weight)| input * weight)
+                        .sum::<f32>();
+
+                    activation.apply(neuron.bias + output)
+                })
+                .collect()
+        })
+    }
+}
+
+fn propagate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("propagate");
+
+    for (inputs, hidden) in TOPOLOGIES {
+        let network = network(inputs, hidden);
+        let vision: Vec<f32> = (0..inputs).map(|n| n as f32 / inputs as f32).collect();
+        let id = format!("{}x{}x2", inputs, hidden);
+        let baseline = Baseline::new(&network);
+
+        assert_eq!(baseline.propagate(vision.clone()), network.propagate(vision.clone()));
+
+        group.bench_with_input(BenchmarkId::new("baseline", &id), &vision, |b, vision| {
+            b.iter(|| baseline.propagate(black_box(vision.clone())))
+        });
+
+        group.bench_with_input(BenchmarkId::new("propagate", &id), &vision, |b, vision| {
+            b.iter(|| network.propagate(black_box(vision.clone())))
+        });
+
+        group.bench_with_input(BenchmarkId::new("propagate_into", &id), &vision, |b, vision| {
+            let mut scratch = Scratch::new();
+
+            b.iter(|| {
+                network.propagate_into(black_box(vision), &mut scratch)[0]
+            })
+        });
+
+        let quantized = network.quantize().unwrap();
+
+        group.bench_with_input(BenchmarkId::new("quantized", &id), &vision, |b, vision| {
+            b.iter(|| quantized.propagate(black_box(vision.clone())))
+        });
+
+        group.bench_with_input(BenchmarkId::new("quantized_into", &id), &vision, |b, vision| {
+            let mut scratch = Scratch::new();
+
+            b.iter(|| {
+                quantized.propagate_into(black_box(vision), &mut scratch)[0]
+            })
+        });
+
+        let frames: Vec<f32> = (0..BATCH).flat_map(|_| vision.iter().copied()).collect();
+
+        group.bench_with_input(BenchmarkId::new("propagate_batch", &id), &frames, |b, frames| {
+            let mut scratch = Scratch::new();
+
+            b.iter(|| {
+                network.propagate_batch_into(black_box(frames), BATCH, &mut scratch)[0]
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("quantized_batch", &id), &frames, |b, frames| {
+            let mut scratch = Scratch::new();
+
+            b.iter(|| {
+                quantized.propagate_batch_into(black_box(frames), BATCH, &mut scratch)[0]
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, propagate);
+criterion_main!(benches);
@@ -0,0 +1,239 @@
+use std::fmt;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Function applied to a neuron's weighted sum (plus bias) to produce its
+/// output; every neuron of a layer shares the same activation.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Activation {
+    /// `max(0, x)` - the network's original (and default) activation
+    #[default]
+    ReLU,
+
+    /// Like `ReLU`, but negative inputs are scaled by the given slope
+    /// instead of being zeroed, e.g. `LeakyReLU(0.01)`
+    LeakyReLU(f32),
+
+    /// `1 / (1 + e^-x)`, squashes into <0, 1>
+    Sigmoid,
+
+    /// Squashes into <-1, 1> - handy for outputs that need a sign, like
+    /// steering
+    Tanh,
+
+    /// Passes the weighted sum through unchanged
+    Identity,
+
+    /// `x / (1 + |x|)`, a cheaper tanh-like curve
+    Softsign,
+
+    /// User-supplied function, see `Activation::custom()`; it cannot be
+    /// saved to disk, since there's no way to serialize a closure
+    #[serde(skip)]
+    Custom(CustomActivation),
+}
+
+impl Activation {
+    /// Wraps an arbitrary function as an activation.
+    ///
+    /// The name is only used for `Debug` output, so that two different
+    /// closures can be told apart when printing a network.
+    pub fn custom(
+        name: impl Into<String>,
+        f: impl Fn(f32) -> f32 + Send + Sync + 'static,
+    ) -> Self {
+        Self::Custom(CustomActivation {
+            name: name.into(),
+            f: Arc::new(f),
+        })
+    }
+
+    /// Custom activations work on `f32`, so for other scalars the value
+    /// gets converted there and back.
+    pub fn apply<T: Scalar>(&self, x: T) -> T {
+        match self {
+            Self::ReLU => x.max(T::ZERO),
+            Self::LeakyReLU(slope) => {
+                if x >= T::ZERO {
+                    x
+                } else {
+                    T::from_f32(*slope) * x
+                }
+            }
+            Self::Sigmoid => T::ONE / (T::ONE + (-x).exp()),
+            Self::Tanh => x.tanh(),
+            Self::Identity => x,
+            Self::Softsign => x / (T::ONE + x.abs()),
+            Self::Custom(custom) => T::from_f32((custom.f)(x.to_f32())),
+        }
+    }
+
+    /// Slope of `apply()` at given point, as needed by backpropagation.
+    ///
+    /// Custom activations don't come with their derivative, so it's
+    /// approximated numerically (with a central difference).
+    pub fn derivative(&self, x: f32) -> f32 {
+        match self {
+            Self::ReLU => {
+                if x > 0.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Self::LeakyReLU(slope) => {
+                if x >= 0.0 {
+                    1.0
+                } else {
+                    *slope
+                }
+            }
+            Self::Sigmoid => {
+                let y = self.apply(x);
+
+                y * (1.0 - y)
+            }
+            Self::Tanh => 1.0 - x.tanh().powi(2),
+            Self::Identity => 1.0,
+            Self::Softsign => 1.0 / (1.0 + x.abs()).powi(2),
+            Self::Custom(custom) => {
+                const H: f32 = 1e-3;
+
+                ((custom.f)(x + H) - (custom.f)(x - H)) / (2.0 * H)
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct CustomActivation {
+    name: String,
+    f: Arc<dyn Fn(f32) -> f32 + Send + Sync>,
+}
+
+impl CustomActivation {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+// Two custom activations are only considered equal when they share the very
+// same function, since closures cannot be compared
+impl PartialEq for CustomActivation {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && Arc::ptr_eq(&self.f, &other.f)
+    }
+}
+
+impl fmt::Debug for CustomActivation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CustomActivation").field(&self.name).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod apply {
+        use super::*;
+
+        #[test]
+        fn relu() {
+            approx::assert_relative_eq!(Activation::ReLU.apply(-2.0_f32), 0.0);
+            approx::assert_relative_eq!(Activation::ReLU.apply(2.0_f32), 2.0);
+        }
+
+        #[test]
+        fn leaky_relu() {
+            approx::assert_relative_eq!(Activation::LeakyReLU(0.1).apply(-2.0_f32), -0.2);
+            approx::assert_relative_eq!(Activation::LeakyReLU(0.1).apply(2.0_f32), 2.0);
+        }
+
+        #[test]
+        fn sigmoid() {
+            approx::assert_relative_eq!(Activation::Sigmoid.apply(0.0_f32), 0.5);
+            approx::assert_relative_eq!(Activation::Sigmoid.apply(2.0_f32), 0.880797);
+        }
+
+        #[test]
+        fn tanh() {
+            approx::assert_relative_eq!(Activation::Tanh.apply(-1.0_f32), -0.7615942);
+            approx::assert_relative_eq!(Activation::Tanh.apply(1.0_f32), 0.7615942);
+        }
+
+        #[test]
+        fn identity() {
+            approx::assert_relative_eq!(Activation::Identity.apply(-3.5_f32), -3.5);
+        }
+
+        #[test]
+        fn softsign() {
+            approx::assert_relative_eq!(Activation::Softsign.apply(-1.0_f32), -0.5);
+            approx::assert_relative_eq!(Activation::Softsign.apply(3.0_f32), 0.75);
+        }
+
+        #[test]
+        fn fixed() {
+            let x = Fixed::from_f32(2.0);
+
+            approx::assert_relative_eq!(Activation::Sigmoid.apply(x).to_f32(), 0.880797, epsilon = 1e-4);
+            approx::assert_relative_eq!(Activation::Tanh.apply(-x).to_f32(), -0.9640276, epsilon = 1e-4);
+            approx::assert_relative_eq!(Activation::LeakyReLU(0.5).apply(-x).to_f32(), -1.0);
+        }
+
+        #[test]
+        fn custom() {
+            let activation = Activation::custom("double", |x| 2.0 * x);
+
+            approx::assert_relative_eq!(activation.apply(-1.5_f32), -3.0);
+        }
+    }
+
+    mod derivative {
+        use super::*;
+
+        #[test]
+        fn relu() {
+            approx::assert_relative_eq!(Activation::ReLU.derivative(-2.0), 0.0);
+            approx::assert_relative_eq!(Activation::ReLU.derivative(2.0), 1.0);
+        }
+
+        #[test]
+        fn leaky_relu() {
+            approx::assert_relative_eq!(Activation::LeakyReLU(0.1).derivative(-2.0), 0.1);
+            approx::assert_relative_eq!(Activation::LeakyReLU(0.1).derivative(2.0), 1.0);
+        }
+
+        #[test]
+        fn sigmoid() {
+            approx::assert_relative_eq!(Activation::Sigmoid.derivative(0.0), 0.25);
+        }
+
+        #[test]
+        fn tanh() {
+            approx::assert_relative_eq!(Activation::Tanh.derivative(0.0), 1.0);
+            approx::assert_relative_eq!(Activation::Tanh.derivative(1.0), 0.41997434);
+        }
+
+        #[test]
+        fn identity() {
+            approx::assert_relative_eq!(Activation::Identity.derivative(-3.5), 1.0);
+        }
+
+        #[test]
+        fn softsign() {
+            approx::assert_relative_eq!(Activation::Softsign.derivative(1.0), 0.25);
+        }
+
+        #[test]
+        fn custom() {
+            let activation = Activation::custom("square", |x| x * x);
+
+            approx::assert_relative_eq!(activation.derivative(1.5), 3.0, epsilon = 1e-3);
+        }
+    }
+}
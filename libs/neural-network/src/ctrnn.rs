@@ -0,0 +1,430 @@
+use rand::Rng;
+
+use crate::*;
+
+/// Shape of a `Ctrnn`: how many inputs it reads, how many neurons it has got
+/// and how many of them (the last ones) are reported as outputs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CtrnnTopology {
+    pub inputs: usize,
+    pub neurons: usize,
+    pub outputs: usize,
+
+    /// Time that passes during a single `propagate()`, i.e. the Euler step
+    pub step_size: f32,
+}
+
+impl CtrnnTopology {
+    pub fn new(inputs: usize, neurons: usize, outputs: usize) -> Self {
+        Self {
+            inputs,
+            neurons,
+            outputs,
+            step_size: 0.1,
+        }
+    }
+
+    pub fn with_step_size(mut self, step_size: f32) -> Self {
+        assert!(step_size > 0.0);
+
+        self.step_size = step_size;
+        self
+    }
+
+    /// Number of values `Ctrnn::weights()` returns - that is: bias, time
+    /// constant, one weight per input and one weight per neuron, per neuron.
+    pub fn weights_len(&self) -> usize {
+        self.neurons * (2 + self.inputs + self.neurons)
+    }
+
+    fn validate(&self) -> Result<(), NetworkError> {
+        for (layer, neurons) in [self.inputs, self.neurons, self.outputs].into_iter().enumerate() {
+            if neurons == 0 {
+                return Err(NetworkError::ZeroWidthLayer { layer });
+            }
+        }
+
+        if self.outputs > self.neurons {
+            return Err(NetworkError::OutputsExceedNeurons {
+                outputs: self.outputs,
+                neurons: self.neurons,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Continuous-time recurrent neural network - every neuron is connected to
+/// every other neuron (and itself), and its state evolves according to:
+///
+///   tau_i * dy_i/dt = -y_i + sum_j(w_ij * tanh(y_j + bias_j)) + sum_k(v_ik * x_k)
+///
+/// which gets integrated with a single Euler step per `propagate()`. Outputs
+/// are firing rates (`tanh(y_i + bias_i)`) of the last `outputs` neurons, so
+/// they're within <-1, 1>.
+#[derive(Clone, Debug)]
+pub struct Ctrnn {
+    topology: CtrnnTopology,
+    biases: Vec<f32>,
+    time_constants: Vec<f32>,
+
+    // Row-major, `inputs + neurons` wide - row `i` holds weights of the
+    // `i`-th neuron: input ones first, recurrent ones follow
+    weights: Vec<f32>,
+
+    state: Vec<f32>,
+}
+
+impl Ctrnn {
+    pub fn random(rng: &mut dyn rand::RngCore, topology: &CtrnnTopology) -> Self {
+        Self::try_random(rng, topology).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_random(rng: &mut dyn rand::RngCore, topology: &CtrnnTopology) -> Result<Self, NetworkError> {
+        topology.validate()?;
+
+        let width = 2 + topology.inputs + topology.neurons;
+
+        // Time constants (the second value of every neuron) are drawn from
+        // <1, 5> - ones drawn from <-1, 1> would mostly get clamped up to the
+        // step size, making neurons forget everything right away
+        let weights = (0..topology.weights_len())
+            .map(|idx| {
+                if idx % width == 1 {
+                    rng.gen_range(1.0..=5.0)
+                } else {
+                    rng.gen_range(-1.0..=1.0)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Self::try_from_weights(topology, weights)
+    }
+
+    pub fn from_weights(topology: &CtrnnTopology, weights: impl IntoIterator<Item = f32>) -> Self {
+        Self::try_from_weights(topology, weights).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Builds a network out of `weights()`-like genes.
+    ///
+    /// Time constants smaller than the topology's step size (including the
+    /// negative ones) get clamped up to it, since the Euler step would
+    /// overshoot otherwise - so `weights()` yields them clamped as well.
+    pub fn try_from_weights(
+        topology: &CtrnnTopology,
+        weights: impl IntoIterator<Item = f32>,
+    ) -> Result<Self, NetworkError> {
+        topology.validate()?;
+
+        let genes: Vec<f32> = weights.into_iter().collect();
+        let expected = topology.weights_len();
+
+        if genes.len() < expected {
+            return Err(NetworkError::NotEnoughWeights { expected, found: genes.len() });
+        }
+
+        if genes.len() > expected {
+            return Err(NetworkError::TooManyWeights { expected, found: genes.len() });
+        }
+
+        if let Some(index) = genes.iter().position(|gene| !gene.is_finite()) {
+            return Err(NetworkError::NonFiniteWeight { index, value: genes[index] });
+        }
+
+        let mut biases = Vec::with_capacity(topology.neurons);
+        let mut time_constants = Vec::with_capacity(topology.neurons);
+        let mut weights = Vec::with_capacity(topology.neurons * (topology.inputs + topology.neurons));
+
+        for neuron in genes.chunks_exact(2 + topology.inputs + topology.neurons) {
+            biases.push(neuron[0]);
+            time_constants.push(neuron[1].max(topology.step_size));
+            weights.extend_from_slice(&neuron[2..]);
+        }
+
+        Ok(Self {
+            topology: topology.clone(),
+            biases,
+            time_constants,
+            weights,
+            state: vec![0.0; topology.neurons],
+        })
+    }
+
+    /// Flattens this network into a genome, neuron by neuron: bias, time
+    /// constant, input weights and recurrent weights.
+    pub fn weights(&self) -> Vec<f32> {
+        let mut weights = Vec::with_capacity(self.topology.weights_len());
+
+        for ((bias, time_constant), row) in self
+            .biases
+            .iter()
+            .zip(&self.time_constants)
+            .zip(self.rows())
+        {
+            weights.push(*bias);
+            weights.push(*time_constant);
+            weights.extend_from_slice(row);
+        }
+
+        weights
+    }
+
+    pub fn topology(&self) -> &CtrnnTopology {
+        &self.topology
+    }
+
+    /// Advances the network by a single Euler step and returns its outputs.
+    pub fn propagate(&mut self, inputs: Vec<f32>) -> Vec<f32> {
+        self.propagate_into(&inputs, &mut Scratch::new()).to_vec()
+    }
+
+    /// Allocation-free version of `propagate()`.
+    pub fn propagate_into<'a>(&mut self, inputs: &[f32], scratch: &'a mut Scratch) -> &'a [f32] {
+        let Scratch { front, back, .. } = scratch;
+
+        self.firing_rates(back);
+        front.clone_from(&self.state);
+        self.integrate(inputs, back, front);
+        std::mem::swap(&mut self.state, front);
+
+        self.firing_rates(front);
+        front.drain(..self.topology.neurons - self.topology.outputs);
+
+        front
+    }
+
+    /// Like `propagate()`, but returns state of every neuron as a single
+    /// layer: membrane potentials plus biases before the activation, firing
+    /// rates after it - outputs are the last `outputs` of those rates.
+    pub fn propagate_traced(&mut self, inputs: Vec<f32>) -> Trace {
+        let mut rates = Vec::new();
+        let mut state = self.state.clone();
+
+        self.firing_rates(&mut rates);
+        self.integrate(&inputs, &rates, &mut state);
+        self.state = state;
+        self.firing_rates(&mut rates);
+
+        let pre_activation = self
+            .state
+            .iter()
+            .zip(&self.biases)
+            .map(|(state, bias)| state + bias)
+            .collect();
+
+        Trace {
+            inputs,
+            layers: vec![LayerTrace {
+                pre_activation,
+                post_activation: rates,
+            }],
+        }
+    }
+
+    /// Evaluates many input vectors at once; each of them is integrated from
+    /// the current state, which is left untouched.
+    pub fn propagate_batch(&self, inputs: &[f32], batch: usize) -> Vec<f32> {
+        assert_eq!(inputs.len(), batch * self.topology.inputs);
+
+        let mut rates = Vec::new();
+        let mut state = Vec::new();
+        let mut outputs = Vec::with_capacity(batch * self.topology.outputs);
+
+        self.firing_rates(&mut rates);
+
+        for inputs in inputs.chunks_exact(self.topology.inputs) {
+            state.clone_from(&self.state);
+            self.integrate(inputs, &rates, &mut state);
+
+            let skip = self.topology.neurons - self.topology.outputs;
+
+            outputs.extend(
+                state
+                    .iter()
+                    .zip(&self.biases)
+                    .skip(skip)
+                    .map(|(state, bias)| (state + bias).tanh()),
+            );
+        }
+
+        outputs
+    }
+
+    /// Brings all neurons back to their resting state.
+    pub fn reset_state(&mut self) {
+        self.state.iter_mut().for_each(|state| *state = 0.0);
+    }
+
+    fn rows(&self) -> impl Iterator<Item = &[f32]> {
+        self.weights.chunks_exact(self.topology.inputs + self.topology.neurons)
+    }
+
+    fn firing_rates(&self, rates: &mut Vec<f32>) {
+        rates.clear();
+        rates.extend(
+            self.state
+                .iter()
+                .zip(&self.biases)
+                .map(|(state, bias)| (state + bias).tanh()),
+        );
+    }
+
+    // Single Euler step of the equation from `Ctrnn`'s docs; time constants
+    // are never smaller than the step size, see `try_from_weights()`
+    fn integrate(&self, inputs: &[f32], rates: &[f32], state: &mut [f32]) {
+        assert_eq!(inputs.len(), self.topology.inputs);
+
+        let step_size = self.topology.step_size;
+
+        for ((state, row), time_constant) in state.iter_mut().zip(self.rows()).zip(&self.time_constants) {
+            let (input_weights, recurrent_weights) = row.split_at(self.topology.inputs);
+
+            let input = input_weights
+                .iter()
+                .zip(inputs)
+                .map(|(weight, input)| weight * input)
+                .sum::<f32>();
+
+            let recurrent = recurrent_weights
+                .iter()
+                .zip(rates)
+                .map(|(weight, rate)| weight * rate)
+                .sum::<f32>();
+
+            *state += step_size / time_constant * (-*state + recurrent + input);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod weights {
+        use super::*;
+
+        #[test]
+        fn roundtrip() {
+            let topology = CtrnnTopology::new(2, 3, 1);
+            let weights: Vec<f32> = (0..topology.weights_len()).map(|n| n as f32 / 10.0).collect();
+
+            assert_eq!(weights.len(), 3 * (2 + 2 + 3));
+
+            let actual = Ctrnn::from_weights(&topology, weights.clone()).weights();
+
+            approx::assert_relative_eq!(actual.as_slice(), weights.as_slice());
+        }
+
+        #[test]
+        fn clamps_time_constants_to_step_size() {
+            let topology = CtrnnTopology::new(1, 2, 1).with_step_size(0.5);
+            let ctrnn = Ctrnn::from_weights(&topology, vec![0.0, -3.0, 1.0, 0.0, 0.0, 0.0, 0.2, 1.0, 0.0, 0.0]);
+
+            approx::assert_relative_eq!(ctrnn.weights()[1], 0.5);
+            approx::assert_relative_eq!(ctrnn.weights()[6], 0.5);
+        }
+
+        #[test]
+        fn draws_time_constants_along_with_weights() {
+            use rand::SeedableRng;
+            use rand_chacha::ChaCha8Rng;
+
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let topology = CtrnnTopology::new(2, 3, 1);
+            let weights = Ctrnn::random(&mut rng, &topology).weights();
+
+            for neuron in weights.chunks_exact(2 + 2 + 3) {
+                assert!((1.0..=5.0).contains(&neuron[1]), "{:?}", neuron);
+                assert!(neuron.iter().enumerate().all(|(idx, w)| idx == 1 || (-1.0..=1.0).contains(w)));
+            }
+        }
+
+        #[test]
+        fn rejects_too_many_outputs() {
+            assert_eq!(
+                Ctrnn::try_from_weights(&CtrnnTopology::new(2, 1, 2), vec![0.0; 5]).unwrap_err(),
+                NetworkError::OutputsExceedNeurons { outputs: 2, neurons: 1 },
+            );
+        }
+    }
+
+    mod propagate {
+        use super::*;
+
+        // Single neuron with tau = 2.0 and self-connection of 0.5, fed by an
+        // input with weight 1.0
+        fn ctrnn() -> Ctrnn {
+            let topology = CtrnnTopology::new(1, 1, 1).with_step_size(0.5);
+
+            Ctrnn::from_weights(&topology, vec![0.0, 2.0, 1.0, 0.5])
+        }
+
+        #[test]
+        fn integrates_with_euler_step() {
+            let mut ctrnn = ctrnn();
+
+            // y = 0.0 + 0.5 / 2.0 * (-0.0 + 0.5 * tanh(0.0) + 1.0) = 0.25
+            let first = [0.25_f32.tanh()];
+            let actual = ctrnn.propagate(vec![1.0]);
+            approx::assert_relative_eq!(actual.as_slice(), first.as_ref());
+
+            // y = 0.25 + 0.25 * (-0.25 + 0.5 * tanh(0.25) + 1.0)
+            let second = [(0.25 + 0.25 * (-0.25 + 0.5 * 0.25_f32.tanh() + 1.0)).tanh()];
+            let actual = ctrnn.propagate(vec![1.0]);
+            approx::assert_relative_eq!(actual.as_slice(), second.as_ref());
+
+            ctrnn.reset_state();
+
+            let actual = ctrnn.propagate(vec![1.0]);
+            approx::assert_relative_eq!(actual.as_slice(), first.as_ref());
+        }
+
+        #[test]
+        fn traced_matches_propagate() {
+            let mut ctrnn = ctrnn();
+            let mut traced = ctrnn.clone();
+
+            for _ in 0..2 {
+                let expected = ctrnn.propagate(vec![1.0]);
+                let trace = traced.propagate_traced(vec![1.0]);
+
+                approx::assert_relative_eq!(trace.outputs(), expected.as_slice());
+            }
+        }
+
+        #[test]
+        fn batch_does_not_advance_state() {
+            let mut ctrnn = ctrnn();
+            let first = [0.25_f32.tanh()];
+
+            let batch = ctrnn.propagate_batch(&[1.0, 1.0], 2);
+            let expected = [first[0], first[0]];
+            approx::assert_relative_eq!(batch.as_slice(), expected.as_ref());
+
+            let actual = ctrnn.propagate(vec![1.0]);
+            approx::assert_relative_eq!(actual.as_slice(), first.as_ref());
+        }
+
+        #[test]
+        fn reports_last_neurons_as_outputs() {
+            let topology = CtrnnTopology::new(1, 3, 2).with_step_size(1.0);
+
+            // Neurons don't talk to each other; their biases are 0.0, 0.1 and
+            // 0.2 and they all relax right away (tau = step size)
+            let mut ctrnn = Ctrnn::from_weights(
+                &topology,
+                vec![
+                    0.0, 1.0, 1.0, 0.0, 0.0, 0.0,
+                    0.1, 1.0, 2.0, 0.0, 0.0, 0.0,
+                    0.2, 1.0, 3.0, 0.0, 0.0, 0.0,
+                ],
+            );
+
+            let actual = ctrnn.propagate(vec![1.0]);
+            let expected = [2.1_f32.tanh(), 3.2_f32.tanh()];
+
+            approx::assert_relative_eq!(actual.as_slice(), expected.as_ref());
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use crate::*;
+
+/// How to compare two networks' weights, see `Network::weight_distance()`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WeightDistance {
+    /// Euclidean distance - grows with every difference, including ones in
+    /// the weights' scale
+    #[default]
+    L2,
+
+    /// `1 - cos(angle)` between the weight vectors, within <0, 2> - ignores
+    /// scale, so a network and its scaled copy are considered the same
+    Cosine,
+}
+
+impl WeightDistance {
+    /// Compares two weight vectors of the same length.
+    pub fn measure(self, a: &[f32], b: &[f32]) -> f32 {
+        assert_eq!(a.len(), b.len());
+
+        match self {
+            Self::L2 => a
+                .iter()
+                .zip(b)
+                .map(|(a, b)| (a - b).powi(2))
+                .sum::<f32>()
+                .sqrt(),
+
+            Self::Cosine => {
+                let norm_a = dot(a, a).sqrt();
+                let norm_b = dot(b, b).sqrt();
+
+                // Zero vector points nowhere, so it's only close to another
+                // zero vector
+                if norm_a == 0.0 || norm_b == 0.0 {
+                    return if norm_a == norm_b { 0.0 } else { 1.0 };
+                }
+
+                (1.0 - dot(a, b) / (norm_a * norm_b)).clamp(0.0, 2.0)
+            }
+        }
+    }
+}
+
+impl<T: Scalar> Network<T> {
+    /// How different the two networks are genotypically, i.e. in terms of
+    /// their `weights()` - both networks must have the same number of them.
+    pub fn weight_distance(&self, other: &Self, metric: WeightDistance) -> f32 {
+        let to_f32 = |weights: Vec<T>| weights.into_iter().map(T::to_f32).collect::<Vec<_>>();
+
+        metric.measure(&to_f32(self.weights()), &to_f32(other.weights()))
+    }
+
+    /// How different the two networks are behaviourally - that is: the
+    /// root-mean-square difference between their outputs for given probes
+    /// (input vectors laid out one after another, like for
+    /// `propagate_batch()`).
+    ///
+    /// Unlike `weight_distance()`, this doesn't care about how the networks
+    /// are built, as long as they agree on their inputs and outputs -
+    /// e.g. two networks with their hidden neurons shuffled are the same.
+    ///
+    /// # Panics
+    ///
+    /// When the networks differ in the number of inputs or outputs, or when
+    /// `probes` are not a whole number of input vectors - just like
+    /// `propagate_batch()` does for the latter.
+    pub fn behavioural_distance(&self, other: &Self, probes: &[T]) -> f32 {
+        assert_eq!(self.input_width(), other.input_width());
+
+        let batch = probes.len() / self.input_width();
+        let ours = self.propagate_batch(probes, batch);
+        let theirs = other.propagate_batch(probes, batch);
+
+        assert_eq!(ours.len(), theirs.len());
+
+        if ours.is_empty() {
+            return 0.0;
+        }
+
+        let sum: f32 = ours
+            .iter()
+            .zip(&theirs)
+            .map(|(&a, &b)| (a.to_f32() - b.to_f32()).powi(2))
+            .sum();
+
+        (sum / ours.len() as f32).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Single neuron: relu(bias + weight * x)
+    fn neuron(bias: f32, weight: f32) -> Network {
+        Network::from_weights(&[LayerTopology::new(1), LayerTopology::new(1)], vec![bias, weight])
+    }
+
+    mod measure {
+        use super::*;
+
+        #[test]
+        fn l2() {
+            let actual = WeightDistance::L2.measure(&[1.0, 2.0, 3.0], &[1.0, -1.0, -1.0]);
+
+            approx::assert_relative_eq!(actual, 5.0);
+        }
+
+        #[test]
+        fn cosine() {
+            let metric = WeightDistance::Cosine;
+
+            approx::assert_relative_eq!(metric.measure(&[1.0, 2.0], &[2.0, 4.0]), 0.0);
+            approx::assert_relative_eq!(metric.measure(&[1.0, 0.0], &[0.0, 3.0]), 1.0);
+            approx::assert_relative_eq!(metric.measure(&[1.0, 2.0], &[-1.0, -2.0]), 2.0);
+        }
+
+        #[test]
+        fn cosine_with_zero_vectors() {
+            let metric = WeightDistance::Cosine;
+
+            approx::assert_relative_eq!(metric.measure(&[0.0, 0.0], &[0.0, 0.0]), 0.0);
+            approx::assert_relative_eq!(metric.measure(&[0.0, 0.0], &[1.0, 0.0]), 1.0);
+        }
+    }
+
+    mod weight_distance {
+        use super::*;
+
+        #[test]
+        fn test() {
+            let a = neuron(0.0, 1.0);
+            let b = neuron(3.0, 5.0);
+
+            approx::assert_relative_eq!(a.weight_distance(&b, WeightDistance::L2), 5.0);
+            approx::assert_relative_eq!(a.weight_distance(&a, WeightDistance::Cosine), 0.0);
+        }
+    }
+
+    mod behavioural_distance {
+        use super::*;
+
+        #[test]
+        fn test() {
+            let a = neuron(0.0, 1.0);
+            let b = neuron(0.0, 2.0);
+
+            // Outputs: 0.0 vs 0.0, then 1.0 vs 2.0, then 3.0 vs 6.0
+            let actual = a.behavioural_distance(&b, &[-1.0, 1.0, 3.0]);
+
+            approx::assert_relative_eq!(actual, (10.0_f32 / 3.0).sqrt());
+        }
+
+        #[test]
+        fn ignores_differences_that_dont_matter() {
+            // Both neurons are dead for negative inputs
+            let a = neuron(0.0, 1.0);
+            let b = neuron(-1.0, 5.0);
+
+            approx::assert_relative_eq!(a.behavioural_distance(&b, &[-3.0, -1.0]), 0.0);
+            assert!(a.weight_distance(&b, WeightDistance::L2) > 4.0);
+        }
+
+        #[test]
+        #[should_panic]
+        fn panics_for_different_inputs() {
+            let b = Network::from_weights(&[LayerTopology::new(2), LayerTopology::new(1)], vec![0.0, 1.0, 1.0]);
+
+            neuron(0.0, 1.0).behavioural_distance(&b, &[1.0, 1.0]);
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use std::fmt;
+
+use crate::*;
+
+/// Reasons a network cannot be built from the given topology or weights.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NetworkError {
+    /// Topology needs at least an input and an output layer
+    EmptyTopology,
+
+    /// Layer at given index has got no neurons
+    ZeroWidthLayer { layer: usize },
+
+    /// Layer at given index is fed by one that doesn't come before it
+    InvalidSource { layer: usize, source: usize },
+
+    /// Input groups (layers with no sources) have to come before all of the
+    /// other layers
+    MisplacedInputGroup { layer: usize },
+
+    /// Convolution at given index has got a kernel wider than its inputs (or
+    /// an empty one), or its neurons are not a multiple of its inputs
+    InvalidConvolution { layer: usize },
+
+    /// Layer at given index is of a kind the network doesn't support, e.g.
+    /// a recurrent one in a `PlasticNetwork`
+    UnsupportedLayerKind { layer: usize, kind: LayerKind },
+
+    /// `Ctrnn` cannot report more outputs than it's got neurons
+    OutputsExceedNeurons { outputs: usize, neurons: usize },
+
+    NotEnoughWeights { expected: usize, found: usize },
+    TooManyWeights { expected: usize, found: usize },
+
+    /// Mask has got to have a bit per weight, see `Network::set_mask()`
+    MaskLength { expected: usize, found: usize },
+
+    /// Weight at given index (in `Network::weights()` order) is NaN or
+    /// infinite - e.g. a mutation overflowed
+    NonFiniteWeight { index: usize, value: f32 },
+
+    /// Initializer's limit or sigma is negative or not finite
+    InvalidInitializer { initializer: Initializer },
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyTopology => {
+                write!(f, "topology must have at least two layers")
+            }
+            Self::ZeroWidthLayer { layer } => {
+                write!(f, "layer {} has got no neurons", layer)
+            }
+            Self::InvalidSource { layer, source } => write!(
+                f,
+                "layer {} cannot be fed by layer {}, which doesn't come before it",
+                layer, source
+            ),
+            Self::MisplacedInputGroup { layer } => {
+                write!(f, "layer {} is an input group, but follows non-input layers", layer)
+            }
+            Self::InvalidConvolution { layer } => write!(
+                f,
+                "layer {} is a convolution whose kernel or neurons don't fit its inputs",
+                layer
+            ),
+            Self::UnsupportedLayerKind { layer, kind } => {
+                write!(f, "layer {} is {:?}, which is not supported here", layer, kind)
+            }
+            Self::OutputsExceedNeurons { outputs, neurons } => write!(
+                f,
+                "cannot have {} outputs with only {} neurons",
+                outputs, neurons
+            ),
+            Self::NotEnoughWeights { expected, found } => write!(
+                f,
+                "got not enough weights (expected {}, got {})",
+                expected, found
+            ),
+            Self::TooManyWeights { expected, found } => write!(
+                f,
+                "got too many weights (expected {}, got {})",
+                expected, found
+            ),
+            Self::MaskLength { expected, found } => write!(
+                f,
+                "mask has got {} bits, but the network has got {} weights",
+                found, expected
+            ),
+            Self::NonFiniteWeight { index, value } => {
+                write!(f, "weight #{} is not finite ({})", index, value)
+            }
+            Self::InvalidInitializer { initializer } => write!(
+                f,
+                "{:?} has got a negative or non-finite parameter",
+                initializer
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
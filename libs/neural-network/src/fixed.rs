@@ -0,0 +1,272 @@
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::*;
+
+/// Q16.16 fixed-point number - 16 bits of integer part, 16 bits of fraction
+/// (a resolution of about 0.000015), spanning roughly <-32768, 32768).
+///
+/// All of its operations, including `exp()` and `tanh()`, are done on plain
+/// integers, so they yield the very same bits everywhere - natively, in
+/// WebAssembly and in between.
+///
+/// Instead of overflowing, operations saturate at `Fixed::MIN` and
+/// `Fixed::MAX` (same goes for division by zero).
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fixed(i32);
+
+const FRAC_BITS: u32 = 16;
+
+impl Fixed {
+    pub const MIN: Self = Self(i32::MIN);
+    pub const MAX: Self = Self(i32::MAX);
+
+    /// Creates a number from its raw representation, i.e. `value * 2^16`.
+    pub const fn from_bits(bits: i32) -> Self {
+        Self(bits)
+    }
+
+    pub const fn to_bits(self) -> i32 {
+        self.0
+    }
+
+    fn saturate(bits: i64) -> Self {
+        Self(bits.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+    }
+}
+
+impl Add for Fixed {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::saturate((self.0 as i64 * rhs.0 as i64) >> FRAC_BITS)
+    }
+}
+
+impl Div for Fixed {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        if rhs.0 == 0 {
+            return match self.0.signum() {
+                1 => Self::MAX,
+                -1 => Self::MIN,
+                _ => Self::ZERO,
+            };
+        }
+
+        Self::saturate(((self.0 as i64) << FRAC_BITS) / rhs.0 as i64)
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(self.0.saturating_neg())
+    }
+}
+
+impl Sum for Fixed {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, Add::add)
+    }
+}
+
+impl Scalar for Fixed {
+    const ZERO: Self = Self(0);
+    const ONE: Self = Self(1 << FRAC_BITS);
+
+    fn from_f64(value: f64) -> Self {
+        // `as` saturates (and turns NaN into zero) on its own
+        Self((value * (1 << FRAC_BITS) as f64).round() as i32)
+    }
+
+    fn to_f64(self) -> f64 {
+        self.0 as f64 / (1 << FRAC_BITS) as f64
+    }
+
+    fn abs(self) -> Self {
+        Self(self.0.saturating_abs())
+    }
+
+    fn max(self, other: Self) -> Self {
+        Ord::max(self, other)
+    }
+
+    // e^x = 2^(x * log2(e)) = 2^k * 2^f, where k is an integer and f lies
+    // within <0, 1) - 2^k is a shift, while 2^f = e^(f * ln(2)) comes from a
+    // few terms of its Taylor series
+    fn exp(self) -> Self {
+        const LOG2_E: i64 = 94_548;
+        const LN_2: i64 = 45_426;
+
+        let y = (self.0 as i64 * LOG2_E) >> FRAC_BITS;
+        let k = y >> FRAC_BITS;
+        let f = y & ((1 << FRAC_BITS) - 1);
+
+        if k >= 15 {
+            return Self::MAX;
+        }
+
+        if k < -(FRAC_BITS as i64) {
+            return Self::ZERO;
+        }
+
+        let t = (f * LN_2) >> FRAC_BITS;
+        let mut term = 1 << FRAC_BITS;
+        let mut sum = term;
+
+        for n in 1..=7 {
+            term = ((term * t) >> FRAC_BITS) / n;
+            sum += term;
+        }
+
+        Self::saturate(if k >= 0 { sum << k } else { sum >> -k })
+    }
+
+    // tanh(|x|) = (1 - e^(-2|x|)) / (1 + e^(-2|x|)), which - unlike the
+    // usual formula - cannot overflow
+    fn tanh(self) -> Self {
+        let e = (-(self.abs() + self.abs())).exp();
+        let result = (Self::ONE - e) / (Self::ONE + e);
+
+        if self.0 < 0 {
+            -result
+        } else {
+            result
+        }
+    }
+
+    fn is_finite(self) -> bool {
+        true
+    }
+}
+
+impl fmt::Debug for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Fixed({})", self.to_f64())
+    }
+}
+
+impl fmt::Display for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.to_f64(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixed(value: f64) -> Fixed {
+        Fixed::from_f64(value)
+    }
+
+    mod conversions {
+        use super::*;
+
+        #[test]
+        fn roundtrip() {
+            for value in [0.0, 1.0, -1.0, 0.5, -123.25, 32767.0] {
+                assert_eq!(fixed(value).to_f64(), value);
+            }
+        }
+
+        #[test]
+        fn rounds_to_nearest() {
+            assert_eq!(fixed(1.0 / 3.0).to_bits(), 21845);
+            assert_eq!(fixed(-1.0 / 3.0).to_bits(), -21845);
+        }
+
+        #[test]
+        fn saturates() {
+            assert_eq!(fixed(1e9), Fixed::MAX);
+            assert_eq!(fixed(-1e9), Fixed::MIN);
+            assert_eq!(fixed(f64::NAN), Fixed::ZERO);
+        }
+    }
+
+    mod arithmetic {
+        use super::*;
+
+        #[test]
+        fn test() {
+            assert_eq!(fixed(1.5) + fixed(2.25), fixed(3.75));
+            assert_eq!(fixed(1.5) - fixed(2.25), fixed(-0.75));
+            assert_eq!(fixed(1.5) * fixed(-2.25), fixed(-3.375));
+            assert_eq!(fixed(-3.375) / fixed(1.5), fixed(-2.25));
+            assert_eq!(-fixed(1.5), fixed(-1.5));
+        }
+
+        #[test]
+        fn saturates() {
+            assert_eq!(Fixed::MAX + Fixed::ONE, Fixed::MAX);
+            assert_eq!(Fixed::MIN - Fixed::ONE, Fixed::MIN);
+            assert_eq!(fixed(30000.0) * fixed(-2.0), Fixed::MIN);
+            assert_eq!(fixed(1.0) / Fixed::ZERO, Fixed::MAX);
+            assert_eq!(-Fixed::MIN, Fixed::MAX);
+        }
+    }
+
+    mod exp {
+        use super::*;
+
+        #[test]
+        fn matches_floats() {
+            for n in -120..=100 {
+                let x = n as f64 / 10.0;
+                let expected = x.exp();
+                let actual = fixed(x).exp().to_f64();
+
+                approx::assert_relative_eq!(actual, expected, epsilon = 1e-4, max_relative = 1e-3);
+            }
+        }
+
+        #[test]
+        fn saturates() {
+            assert_eq!(fixed(11.0).exp(), Fixed::MAX);
+            assert_eq!(fixed(-20.0).exp(), Fixed::ZERO);
+        }
+    }
+
+    mod tanh {
+        use super::*;
+
+        #[test]
+        fn matches_floats() {
+            for n in -100..=100 {
+                let x = n as f64 / 10.0;
+                let actual = fixed(x).tanh().to_f64();
+
+                approx::assert_relative_eq!(actual, x.tanh(), epsilon = 1e-4);
+            }
+        }
+
+        #[test]
+        fn is_odd() {
+            for n in 0..100 {
+                let x = fixed(n as f64 / 7.0);
+
+                assert_eq!(x.tanh(), -(-x).tanh());
+            }
+        }
+    }
+}
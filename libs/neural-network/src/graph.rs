@@ -0,0 +1,152 @@
+use crate::*;
+
+// Networks with skip connections (or many groups of inputs) cannot just pass
+// each layer's outputs on to the next one - instead, outputs of every input
+// group and layer (called nodes here, numbered just like the topology) are
+// kept around, and each layer gathers its inputs from its sources.
+impl<T: Scalar> Network<T> {
+    /// Whether this network is a plain chain - a single group of inputs,
+    /// with every layer fed by the one before it.
+    pub(crate) fn is_chain(&self) -> bool {
+        self.inputs.len() == 1
+            && self
+                .layers
+                .iter()
+                .enumerate()
+                .all(|(idx, layer)| layer.sources == [idx])
+    }
+
+    /// Number of values a single input vector is made of.
+    pub(crate) fn input_width(&self) -> usize {
+        self.inputs.iter().sum()
+    }
+
+    /// Splits `batch` input vectors into their groups, which become the
+    /// first nodes; the remaining nodes get cleared, ready for the layers'
+    /// outputs.
+    pub(crate) fn split_inputs(&self, inputs: &[T], batch: usize, nodes: &mut Vec<Vec<T>>) {
+        split_inputs(&self.inputs, self.layers.len(), inputs, batch, nodes);
+    }
+
+    pub(crate) fn propagate_graph_into<'a>(&self, inputs: &[T], scratch: &'a mut Scratch<T>) -> &'a [T] {
+        let Scratch { front, nodes, state, .. } = scratch;
+        let groups = self.inputs.len();
+        let mut state = self.prepare_state(state);
+
+        self.split_inputs(inputs, 1, nodes);
+
+        for (idx, layer) in self.layers.iter().enumerate() {
+            gather(nodes, &layer.sources, 1, front);
+            layer.propagate_step(front, layer.take_state(&mut state), &mut nodes[groups + idx]);
+        }
+
+        &nodes[groups + self.layers.len() - 1]
+    }
+
+    pub(crate) fn propagate_graph_batch_into<'a>(
+        &self,
+        inputs: &[T],
+        batch: usize,
+        scratch: &'a mut Scratch<T>,
+    ) -> &'a [T] {
+        let Scratch { front, nodes, state, .. } = scratch;
+        let groups = self.inputs.len();
+        let mut state = self.prepare_state(state);
+
+        self.split_inputs(inputs, batch, nodes);
+
+        for (idx, layer) in self.layers.iter().enumerate() {
+            gather(nodes, &layer.sources, batch, front);
+            layer.propagate_batch_into(front, batch, layer.take_state(&mut state), &mut nodes[groups + idx]);
+        }
+
+        &nodes[groups + self.layers.len() - 1]
+    }
+
+    /// Same as `propagate_graph_into()`, but records every layer's values;
+    /// works for chains as well.
+    pub(crate) fn propagate_graph_traced(&self, inputs: &[T], state: &mut Vec<T>) -> Vec<LayerTrace> {
+        let groups = self.inputs.len();
+        let mut state = self.prepare_state(state);
+        let mut nodes = Vec::new();
+        let mut gathered = Vec::new();
+        let mut sums = Vec::new();
+        let mut traces = Vec::with_capacity(self.layers.len());
+
+        self.split_inputs(inputs, 1, &mut nodes);
+
+        for (idx, layer) in self.layers.iter().enumerate() {
+            gather(&nodes, &layer.sources, 1, &mut gathered);
+
+            let outputs = &mut nodes[groups + idx];
+            layer.propagate_traced(&gathered, layer.take_state(&mut state), &mut sums, outputs);
+
+            traces.push(LayerTrace {
+                pre_activation: sums.iter().map(|sum| sum.to_f32()).collect(),
+                post_activation: outputs.iter().map(|output| output.to_f32()).collect(),
+            });
+        }
+
+        traces
+    }
+}
+
+/// See `Network::split_inputs()`; `groups` are widths of the input groups,
+/// followed by `layers` other nodes.
+pub(crate) fn split_inputs<T: Copy>(
+    groups: &[usize],
+    layers: usize,
+    inputs: &[T],
+    batch: usize,
+    nodes: &mut Vec<Vec<T>>,
+) {
+    let width: usize = groups.iter().sum();
+
+    assert_eq!(inputs.len(), batch * width);
+
+    nodes.resize_with(groups.len() + layers, Vec::new);
+
+    for node in nodes.iter_mut() {
+        node.clear();
+    }
+
+    for inputs in inputs.chunks_exact(width) {
+        let mut offset = 0;
+
+        for (node, &group) in nodes.iter_mut().zip(groups) {
+            node.extend_from_slice(&inputs[offset..offset + group]);
+            offset += group;
+        }
+    }
+}
+
+/// Concatenates outputs of the given nodes, one input vector after another.
+pub(crate) fn gather<T: Copy>(nodes: &[Vec<T>], sources: &[usize], batch: usize, into: &mut Vec<T>) {
+    into.clear();
+
+    for sample in 0..batch {
+        for &source in sources {
+            let width = nodes[source].len() / batch;
+
+            into.extend_from_slice(&nodes[source][sample * width..(sample + 1) * width]);
+        }
+    }
+}
+
+/// Inverse of `gather()` - adds slices of `values` onto the given nodes.
+pub(crate) fn scatter<T: Scalar>(values: &[T], sources: &[usize], batch: usize, nodes: &mut [Vec<T>]) {
+    let mut values = values.iter();
+
+    for sample in 0..batch {
+        for &source in sources {
+            let width = nodes[source].len() / batch;
+
+            for (node, &value) in nodes[source][sample * width..(sample + 1) * width]
+                .iter_mut()
+                .zip(&mut values)
+            {
+                *node = *node + value;
+            }
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use rand::{Rng, RngCore};
+use rand_distr::{Distribution, Normal};
+
+/// How `Network::random_with()` draws initial weights and biases; the
+/// fan-in and fan-out based ones scale each layer according to how many
+/// inputs and neurons it's got, so that wide layers don't start saturated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Initializer {
+    /// Uniformly from <-limit, limit> - `Uniform(1.0)` is what
+    /// `Network::random()` uses
+    Uniform(f32),
+
+    /// Xavier/Glorot - uniformly from <-limit, limit>, where `limit =
+    /// sqrt(6 / (fan_in + fan_out))`; suits tanh and sigmoid layers
+    Xavier,
+
+    /// He/Kaiming - normal distribution with `sigma = sqrt(2 / fan_in)`;
+    /// suits ReLU layers
+    He,
+
+    /// Normal distribution with given sigma, centered at zero
+    Normal(f32),
+
+    /// Every weight and bias starts at zero
+    Zeros,
+}
+
+impl Default for Initializer {
+    fn default() -> Self {
+        Self::Uniform(1.0)
+    }
+}
+
+impl Initializer {
+    /// `Uniform(limit)`; panics if the limit is negative or not finite.
+    pub fn uniform(limit: f32) -> Self {
+        assert!(
+            Self::Uniform(limit).is_valid(),
+            "uniform limit must be finite and non-negative, got {}",
+            limit
+        );
+
+        Self::Uniform(limit)
+    }
+
+    /// `Normal(sigma)`; panics if sigma is negative or not finite.
+    pub fn normal(sigma: f32) -> Self {
+        assert!(
+            Self::Normal(sigma).is_valid(),
+            "sigma must be finite and non-negative, got {}",
+            sigma
+        );
+
+        Self::Normal(sigma)
+    }
+
+    /// Whether the parameter (if there's any) makes sense - `Uniform(-1.0)`
+    /// would describe an inverted range and `Normal(-1.0)` a negative sigma.
+    pub(crate) fn is_valid(self) -> bool {
+        match self {
+            Self::Uniform(param) | Self::Normal(param) => param.is_finite() && param >= 0.0,
+            Self::Xavier | Self::He | Self::Zeros => true,
+        }
+    }
+
+    /// Draws a single weight (or bias) for a layer with given number of
+    /// inputs per neuron (`fan_in`) and neurons (`fan_out`).
+    pub(crate) fn sample(self, rng: &mut dyn RngCore, fan_in: usize, fan_out: usize) -> f32 {
+        match self {
+            Self::Uniform(limit) => rng.gen_range(-limit..=limit),
+            Self::Xavier => {
+                let limit = (6.0 / (fan_in + fan_out) as f32).sqrt();
+
+                rng.gen_range(-limit..=limit)
+            }
+            Self::He => Self::Normal((2.0 / fan_in as f32).sqrt()).sample(rng, fan_in, fan_out),
+            Self::Normal(sigma) => Normal::new(0.0, sigma)
+                .expect("sigma must be finite and non-negative")
+                .sample(rng),
+            Self::Zeros => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    fn samples(initializer: Initializer, fan_in: usize, fan_out: usize) -> Vec<f32> {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        (0..10_000)
+            .map(|_| initializer.sample(&mut rng, fan_in, fan_out))
+            .collect()
+    }
+
+    fn std_dev(samples: &[f32]) -> f32 {
+        let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+        let var = samples.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / samples.len() as f32;
+
+        var.sqrt()
+    }
+
+    #[test]
+    fn uniform() {
+        let samples = samples(Initializer::Uniform(0.5), 9, 18);
+
+        assert!(samples.iter().all(|x| x.abs() <= 0.5));
+        assert!(samples.iter().any(|x| x.abs() > 0.49));
+    }
+
+    #[test]
+    fn xavier() {
+        // sqrt(6 / (9 + 18))
+        let limit = 0.47140452;
+        let samples = samples(Initializer::Xavier, 9, 18);
+
+        assert!(samples.iter().all(|x| x.abs() <= limit));
+        assert!(samples.iter().any(|x| x.abs() > limit - 0.01));
+    }
+
+    #[test]
+    fn he() {
+        // sqrt(2 / 50)
+        approx::assert_relative_eq!(std_dev(&samples(Initializer::He, 50, 2)), 0.2, epsilon = 0.01);
+    }
+
+    #[test]
+    fn normal() {
+        approx::assert_relative_eq!(std_dev(&samples(Initializer::Normal(3.0), 1, 1)), 3.0, epsilon = 0.1);
+    }
+
+    #[test]
+    fn zeros() {
+        assert!(samples(Initializer::Zeros, 9, 18).iter().all(|&x| x == 0.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "uniform limit must be finite and non-negative, got -1")]
+    fn rejects_inverted_uniform() {
+        Initializer::uniform(-1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "sigma must be finite and non-negative, got NaN")]
+    fn rejects_invalid_normal() {
+        Initializer::normal(f32::NAN);
+    }
+}
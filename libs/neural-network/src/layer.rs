@@ -0,0 +1,609 @@
+use crate::*;
+
+// Weights are kept in a single row-major matrix - row `n` holds weights of
+// the `n`-th neuron, one per input - so that propagating a layer walks
+// through one contiguous chunk of memory instead of many small `Vec`s.
+//
+// Recurrent layers additionally see their own previous outputs (the state,
+// kept in `Scratch`), so their rows are `inputs + neurons` wide: input weights come first,
+// recurrent ones follow. GRU layers keep three such blocks of rows, one per
+// gate: update, reset and candidate.
+//
+// Convolutions have got a single row per filter, `kernel` weights wide,
+// shared by all of the filter's outputs.
+#[derive(Clone, Debug)]
+pub(crate) struct Layer<T = f32> {
+    pub(crate) inputs: usize,
+
+    // Nodes (input groups and layers, numbered like in the topology) whose
+    // outputs make up this layer's inputs
+    pub(crate) sources: Vec<usize>,
+    pub(crate) neurons: usize,
+    pub(crate) kind: LayerKind,
+    pub(crate) biases: Vec<T>,
+    pub(crate) weights: Vec<T>,
+
+    // Whether given connection (same index as in `weights`) is enabled -
+    // disabled ones always have their weights set to zero, so that training
+    // and `weights()` see them as such
+    pub(crate) mask: Vec<bool>,
+
+    // Columns of the enabled connections, row by row, which is what
+    // propagation walks through instead of whole rows; `None` when every
+    // connection is enabled - see `update_active()`
+    pub(crate) active: Option<Vec<Vec<usize>>>,
+    pub(crate) activation: Activation,
+}
+
+impl<T: Scalar> Layer<T> {
+    pub fn random(
+        rng: &mut dyn rand::RngCore,
+        input_neurons: usize,
+        output: &LayerTopology,
+        initializer: Initializer,
+    ) -> Self {
+        let mut layer = Self::empty(input_neurons, output);
+        let (fan_in, fan_out) = (layer.row_width(), layer.neurons);
+
+        // Drawn neuron by neuron (weights first, then bias), so that seeded
+        // networks stay the same as when each neuron was created on its own
+        for _ in 0..layer.rows_len() {
+            layer
+                .weights
+                .extend((0..fan_in).map(|_| T::from_f32(initializer.sample(rng, fan_in, fan_out))));
+
+            layer.biases.push(T::from_f32(initializer.sample(rng, fan_in, fan_out)));
+        }
+
+        layer
+    }
+
+    fn empty(input_neurons: usize, output: &LayerTopology) -> Self {
+        let kind = output.kind;
+
+        let (rows, width) = match kind {
+            LayerKind::CircularConv { kernel } => (output.neurons / input_neurons, kernel),
+            _ => (
+                kind.gates() * output.neurons,
+                input_neurons + if kind.is_recurrent() { output.neurons } else { 0 },
+            ),
+        };
+
+        Self {
+            inputs: input_neurons,
+            sources: Vec::new(),
+            neurons: output.neurons,
+            kind,
+            biases: Vec::with_capacity(rows),
+            weights: Vec::with_capacity(rows * width),
+            mask: vec![true; rows * width],
+            active: None,
+            activation: output.activation.clone(),
+        }
+    }
+
+    pub(crate) fn with_sources(mut self, sources: Vec<usize>) -> Self {
+        self.sources = sources;
+        self
+    }
+
+    pub fn outputs(&self) -> usize {
+        self.neurons
+    }
+
+    /// Number of values this layer remembers between steps.
+    pub(crate) fn state_len(&self) -> usize {
+        if self.kind.is_recurrent() { self.neurons } else { 0 }
+    }
+
+    /// Splits this layer's part off the front of what's left of a network's
+    /// state (see `Network::prepare_state()`).
+    pub(crate) fn take_state<'a>(&self, state: &mut &'a mut [T]) -> &'a mut [T] {
+        let (taken, rest) = std::mem::take(state).split_at_mut(self.state_len());
+
+        *state = rest;
+        taken
+    }
+
+    fn rows_len(&self) -> usize {
+        match self.kind {
+            LayerKind::CircularConv { .. } => self.neurons / self.inputs,
+            kind => kind.gates() * self.neurons,
+        }
+    }
+
+    pub(crate) fn row_width(&self) -> usize {
+        match self.kind {
+            LayerKind::CircularConv { kernel } => kernel,
+            kind => self.inputs + if kind.is_recurrent() { self.neurons } else { 0 },
+        }
+    }
+
+    pub(crate) fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.weights.chunks_exact(self.row_width())
+    }
+
+    fn row(&self, row: usize) -> &[T] {
+        let width = self.row_width();
+
+        &self.weights[row * width..(row + 1) * width]
+    }
+
+    /// Propagates `batch` row-major input vectors at once - that is: computes
+    /// `inputs * weights^T + biases`, yielding `batch` rows of outputs into
+    /// `outputs` (reusing its allocation).
+    ///
+    /// Recurrent layers evaluate each of the inputs against given state,
+    /// without advancing it.
+    pub(crate) fn propagate_batch_into(
+        &self,
+        inputs: &[T],
+        batch: usize,
+        state: &[T],
+        outputs: &mut Vec<T>,
+    ) {
+        assert_eq!(inputs.len(), batch * self.inputs);
+
+        outputs.clear();
+
+        for inputs in inputs.chunks_exact(self.inputs).take(batch) {
+            match self.kind {
+                LayerKind::Dense if self.active.is_none() => {
+                    outputs.extend(self.rows().zip(&self.biases).map(|(row, bias)| {
+                        // represent input into a node
+                        let output = dot(row, inputs);
+                        // adding bias and applying the layer's activation function
+                        self.activation.apply(*bias + output)
+                    }));
+                }
+
+                LayerKind::Dense | LayerKind::Elman => {
+                    outputs.extend(self.biases.iter().enumerate().map(|(row, bias)| {
+                        self.activation.apply(*bias + self.dot(row, inputs, state))
+                    }));
+                }
+
+                LayerKind::Gru => self.propagate_gru(inputs, state, outputs, None),
+                LayerKind::CircularConv { .. } => self.propagate_conv(inputs, outputs, None),
+            }
+        }
+    }
+
+    /// Propagates a single input vector, then remembers the outputs as the
+    /// state for next time (if this layer is recurrent).
+    pub(crate) fn propagate_step(&self, inputs: &[T], state: &mut [T], outputs: &mut Vec<T>) {
+        self.propagate_batch_into(inputs, 1, state, outputs);
+        state.copy_from_slice(&outputs[..state.len()]);
+    }
+
+    /// Like `propagate_step()`, but additionally records the values the
+    /// layer's activation got applied to.
+    pub(crate) fn propagate_traced(
+        &self,
+        inputs: &[T],
+        state: &mut [T],
+        sums: &mut Vec<T>,
+        outputs: &mut Vec<T>,
+    ) {
+        assert_eq!(inputs.len(), self.inputs);
+
+        sums.clear();
+        outputs.clear();
+
+        match self.kind {
+            LayerKind::Dense | LayerKind::Elman => {
+                sums.extend(
+                    self.biases
+                        .iter()
+                        .enumerate()
+                        .map(|(row, bias)| *bias + self.dot(row, inputs, state)),
+                );
+
+                outputs.extend(sums.iter().map(|&sum| self.activation.apply(sum)));
+            }
+
+            LayerKind::Gru => self.propagate_gru(inputs, state, outputs, Some(sums)),
+            LayerKind::CircularConv { .. } => self.propagate_conv(inputs, outputs, Some(sums)),
+        }
+
+        state.copy_from_slice(&outputs[..state.len()]);
+    }
+
+    // Gates use sigmoid, while the layer's activation is applied to the
+    // candidate state:
+    //
+    //   z = sigmoid(Wz * x + Uz * h + bz)
+    //   r = sigmoid(Wr * x + Ur * h + br)
+    //   c = activation(Wc * x + Uc * (r . h) + bc)
+    //   h' = (1 - z) . h + z . c
+    //
+    // Candidates' sums get recorded into `sums`, if given.
+    fn propagate_gru(
+        &self,
+        inputs: &[T],
+        state: &[T],
+        outputs: &mut Vec<T>,
+        mut sums: Option<&mut Vec<T>>,
+    ) {
+        let n = self.neurons;
+        let start = outputs.len();
+
+        // Both gates are first stashed in `outputs`, to avoid allocating
+        // separate buffers for them - z in the first `n` slots, r in the
+        // following ones
+        for row in 0..2 * n {
+            let sum = self.biases[row] + self.dot(row, inputs, state);
+
+            outputs.push(Activation::Sigmoid.apply(sum));
+        }
+
+        for neuron in 0..n {
+            let row = 2 * n + neuron;
+            let resets = &outputs[start + n..start + 2 * n];
+
+            let sum = if self.active.is_some() {
+                self.biases[row]
+                    + self.masked_sum(row, |column| match column.checked_sub(self.inputs) {
+                        Some(column) => resets[column] * state[column],
+                        None => inputs[column],
+                    })
+            } else {
+                let (input_weights, recurrent_weights) = self.row(row).split_at(self.inputs);
+
+                self.biases[row]
+                    + dot(input_weights, inputs)
+                    + recurrent_weights
+                        .iter()
+                        .zip(resets)
+                        .zip(state)
+                        .map(|((&weight, &reset), &state)| weight * reset * state)
+                        .sum::<T>()
+            };
+
+            if let Some(sums) = &mut sums {
+                sums.push(sum);
+            }
+
+            let candidate = self.activation.apply(sum);
+            let update = outputs[start + neuron];
+
+            outputs[start + neuron] = (T::ONE - update) * state[neuron] + update * candidate;
+        }
+
+        outputs.truncate(start + n);
+    }
+
+    // Output `i` of filter `f` sees `kernel` inputs centered around the
+    // `i`-th one, wrapping around the ends of the ring:
+    //
+    //   y[f * n + i] = activation(b[f] + sum_k w[f][k] * x[(i + k - kernel / 2) mod n])
+    //
+    // Sums get recorded into `sums`, if given.
+    pub(crate) fn propagate_conv(&self, inputs: &[T], outputs: &mut Vec<T>, mut sums: Option<&mut Vec<T>>) {
+        for (row, &bias) in self.biases.iter().enumerate() {
+            for cell in 0..self.inputs {
+                let sum = bias + self.masked_sum(row, |tap| inputs[self.conv_input(cell, tap)]);
+
+                if let Some(sums) = &mut sums {
+                    sums.push(sum);
+                }
+
+                outputs.push(self.activation.apply(sum));
+            }
+        }
+    }
+
+    /// Index of the input that given tap of a convolution's kernel sees when
+    /// computing the output for given cell.
+    pub(crate) fn conv_input(&self, cell: usize, tap: usize) -> usize {
+        let n = self.inputs;
+
+        (cell + n + tap - self.row_width() / 2) % n
+    }
+
+    // Rows of recurrent layers are followed by weights for the state, while
+    // the other layers' rows hold input weights only
+    fn dot(&self, row: usize, inputs: &[T], state: &[T]) -> T {
+        if self.active.is_some() {
+            return self.masked_sum(row, |column| match column.checked_sub(self.inputs) {
+                Some(column) => state[column],
+                None => inputs[column],
+            });
+        }
+
+        let row = self.row(row);
+
+        if !self.kind.is_recurrent() {
+            return dot(row, inputs);
+        }
+
+        let (input_weights, recurrent_weights) = row.split_at(self.inputs);
+
+        dot(input_weights, inputs) + dot(recurrent_weights, state)
+    }
+
+    // Sums `weight * value(column)` over given row's enabled connections
+    fn masked_sum(&self, row: usize, value: impl Fn(usize) -> T) -> T {
+        let weights = self.row(row);
+
+        match &self.active {
+            Some(active) => active[row].iter().map(|&column| weights[column] * value(column)).sum(),
+            None => weights.iter().enumerate().map(|(column, &weight)| weight * value(column)).sum(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn new(neurons: Vec<(T, Vec<T>)>, activation: Activation) -> Self {
+        assert!(!neurons.is_empty());
+
+        let inputs = neurons[0].1.len();
+
+        assert!(neurons.iter().all(|(_, weights)| weights.len() == inputs));
+
+        Self {
+            inputs,
+            sources: Vec::new(),
+            neurons: neurons.len(),
+            kind: LayerKind::Dense,
+            biases: neurons.iter().map(|(bias, _)| *bias).collect(),
+            mask: vec![true; neurons.iter().map(|(_, weights)| weights.len()).sum()],
+            active: None,
+            weights: neurons.into_iter().flat_map(|(_, weights)| weights).collect(),
+            activation,
+        }
+    }
+
+    pub fn from_weights( input_neurons: usize, output: &LayerTopology, weights: &mut dyn Iterator<Item = T>,) -> Self {
+        let mut layer = Self::empty(input_neurons, output);
+
+        for _ in 0..layer.rows_len() {
+            let width = layer.row_width();
+
+            layer.biases.push(weights.next().expect("got not enough weights"));
+            layer.weights.extend(
+                (0..width).map(|_| weights.next().expect("got not enough weights")),
+            );
+        }
+
+        layer
+    }
+
+    /// Appends this layer's weights in the chromosome order: each neuron's
+    /// bias followed by its weights (for GRU: gate by gate).
+    pub(crate) fn extend_weights(&self, weights: &mut Vec<T>) {
+        for (row, bias) in self.rows().zip(&self.biases) {
+            weights.push(*bias);
+            weights.extend_from_slice(row);
+        }
+    }
+
+    /// Appends this layer's mask in the chromosome order; biases cannot be
+    /// disabled, so they're always `true`.
+    pub(crate) fn extend_mask(&self, mask: &mut Vec<bool>) {
+        for row in self.mask.chunks_exact(self.row_width()) {
+            mask.push(true);
+            mask.extend_from_slice(row);
+        }
+    }
+
+    /// Inverse of `extend_mask()` - bits corresponding to biases are
+    /// skipped.
+    pub(crate) fn set_mask(&mut self, mask: &mut dyn Iterator<Item = bool>) {
+        let width = self.row_width();
+
+        for (row, weights) in self.mask.chunks_exact_mut(width).zip(self.weights.chunks_exact_mut(width)) {
+            mask.next();
+
+            for (enabled, weight) in row.iter_mut().zip(weights) {
+                *enabled = mask.next().expect("got not enough mask bits");
+
+                if !*enabled {
+                    *weight = T::ZERO;
+                }
+            }
+        }
+
+        self.update_active();
+    }
+
+    /// Rebuilds `active` after `mask` has changed.
+    pub(crate) fn update_active(&mut self) {
+        self.active = if self.mask.contains(&false) {
+            let rows = self.mask.chunks_exact(self.row_width()).map(|row| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, &enabled)| enabled)
+                    .map(|(column, _)| column)
+                    .collect()
+            });
+
+            Some(rows.collect())
+        } else {
+            None
+        };
+    }
+}
+
+pub(crate) fn dot<T: Scalar>(weights: &[T], values: &[T]) -> T {
+    weights
+        .iter()
+        .zip(values)
+        .map(|(&weight, &value)| weight * value)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod propagate {
+        use super::*;
+
+        #[test]
+        fn test() {
+            let layer = Layer::<f32>::new(vec![(0.5, vec![-0.3, 0.8])], Activation::ReLU);
+            let mut outputs = Vec::new();
+
+            // Ensures `.max()` (our ReLU) works:
+            layer.propagate_batch_into(&[-10.0, -10.0], 1, &[], &mut outputs);
+            approx::assert_relative_eq!(outputs[0], 0.0);
+
+            // `0.5` and `1.0` chosen by a fair dice roll:
+            layer.propagate_batch_into(&[0.5, 1.0], 1, &[], &mut outputs);
+            approx::assert_relative_eq!(outputs[0], (-0.3 * 0.5) + (0.8 * 1.0) + 0.5);
+        }
+
+        #[test]
+        fn test_with_tanh() {
+            let layer = Layer::<f32>::new(vec![(0.5, vec![-0.3, 0.8])], Activation::Tanh);
+            let mut outputs = Vec::new();
+
+            // Unlike ReLU, tanh lets negative sums through:
+            layer.propagate_batch_into(&[-10.0, -10.0], 1, &[], &mut outputs);
+            approx::assert_relative_eq!(
+                outputs[0],
+                ((-0.3 * -10.0) + (0.8 * -10.0) + 0.5_f32).tanh(),
+            );
+        }
+
+        #[test]
+        fn test_with_many_neurons() {
+            let layer = Layer::<f32>::new(
+                vec![(0.0, vec![1.0, 2.0]), (1.0, vec![-1.0, 0.5])],
+                Activation::Identity,
+            );
+            let mut outputs = Vec::new();
+
+            layer.propagate_batch_into(&[2.0, 3.0], 1, &[], &mut outputs);
+            approx::assert_relative_eq!(outputs.as_slice(), [8.0, 0.5].as_ref());
+        }
+    }
+
+    mod propagate_batch_into {
+        use super::*;
+
+        #[test]
+        fn test() {
+            let layer = Layer::<f32>::new(
+                vec![(0.0, vec![1.0, 2.0]), (1.0, vec![-1.0, 0.5])],
+                Activation::Identity,
+            );
+            let mut outputs = Vec::new();
+
+            layer.propagate_batch_into(&[2.0, 3.0, 0.0, -1.0, 1.0, 1.0], 3, &[], &mut outputs);
+            approx::assert_relative_eq!(
+                outputs.as_slice(),
+                [8.0, 0.5, -2.0, 0.5, 3.0, 0.5].as_ref(),
+            );
+        }
+    }
+
+    mod propagate_step {
+        use super::*;
+
+        #[test]
+        fn elman_remembers_previous_outputs() {
+            let topology = LayerTopology::new(1)
+                .with_activation(Activation::Identity)
+                .with_kind(LayerKind::Elman);
+
+            // bias, input weight, recurrent weight
+            let layer = Layer::<f32>::from_weights(1, &topology, &mut vec![0.0, 1.0, 0.5].into_iter());
+            let mut state = vec![0.0];
+            let mut outputs = Vec::new();
+
+            layer.propagate_step(&[2.0], &mut state, &mut outputs);
+            approx::assert_relative_eq!(outputs[0], 2.0);
+
+            // 0.0 * 1.0 + 2.0 * 0.5
+            layer.propagate_step(&[0.0], &mut state, &mut outputs);
+            approx::assert_relative_eq!(outputs[0], 1.0);
+
+            state = vec![0.0];
+            layer.propagate_step(&[0.0], &mut state, &mut outputs);
+            approx::assert_relative_eq!(outputs[0], 0.0);
+        }
+
+        #[test]
+        fn gru_interpolates_between_state_and_candidate() {
+            let topology = LayerTopology::new(1)
+                .with_activation(Activation::Identity)
+                .with_kind(LayerKind::Gru);
+
+            // (bias, input weight, recurrent weight) for update, reset and
+            // candidate; update gate is sigmoid(0) = 0.5, reset gate is
+            // sigmoid(100) ~= 1
+            let weights = vec![0.0, 0.0, 0.0, 100.0, 0.0, 0.0, 0.0, 1.0, 1.0];
+            let layer = Layer::<f32>::from_weights(1, &topology, &mut weights.into_iter());
+            let mut state = vec![0.0];
+            let mut outputs = Vec::new();
+
+            // h = 0.5 * 0.0 + 0.5 * (4.0 + 0.0)
+            layer.propagate_step(&[4.0], &mut state, &mut outputs);
+            approx::assert_relative_eq!(outputs.as_slice(), [2.0].as_ref());
+
+            // h = 0.5 * 2.0 + 0.5 * (4.0 + 2.0)
+            layer.propagate_step(&[4.0], &mut state, &mut outputs);
+            approx::assert_relative_eq!(outputs.as_slice(), [4.0].as_ref());
+        }
+    }
+
+    mod propagate_conv {
+        use super::*;
+
+        fn conv(weights: Vec<f32>) -> Layer<f32> {
+            let topology = LayerTopology::new(8)
+                .with_activation(Activation::Identity)
+                .with_kind(LayerKind::CircularConv { kernel: 3 });
+
+            Layer::from_weights(4, &topology, &mut weights.into_iter())
+        }
+
+        #[test]
+        fn wraps_around() {
+            // (bias, kernel) per filter: the first one copies each cell, the
+            // second one looks at its left neighbour
+            let layer = conv(vec![0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 0.0]);
+            let mut outputs = Vec::new();
+
+            layer.propagate_batch_into(&[1.0, 2.0, 3.0, 4.0], 1, &[], &mut outputs);
+
+            let expected = [1.0, 2.0, 3.0, 4.0, 5.0, 2.0, 3.0, 4.0];
+            approx::assert_relative_eq!(outputs.as_slice(), expected.as_ref());
+        }
+
+        #[test]
+        fn shifting_inputs_shifts_outputs() {
+            let layer = conv(vec![0.1, -0.5, 0.8, 0.3, -0.2, 0.4, 0.9, -0.7]);
+            let (mut outputs, mut shifted) = (Vec::new(), Vec::new());
+
+            layer.propagate_batch_into(&[0.2, 0.9, 0.0, 0.4], 1, &[], &mut outputs);
+            layer.propagate_batch_into(&[0.4, 0.2, 0.9, 0.0], 1, &[], &mut shifted);
+
+            for filter in 0..2 {
+                for cell in 0..4 {
+                    approx::assert_relative_eq!(shifted[filter * 4 + (cell + 1) % 4], outputs[filter * 4 + cell]);
+                }
+            }
+        }
+    }
+
+    // due to the random neuron initialization, this test is not deterministic and the numbers need to be reproduced by first running the test
+    // and failing it the plugging in those numbers
+    mod random {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use super::*;
+
+        #[test]
+        fn test() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let layer = Layer::<f32>::random(&mut rng, 4, &LayerTopology::new(1), Initializer::default());
+
+            approx::assert_relative_eq!(layer.biases.as_slice(), [0.5238807].as_ref());
+
+            approx::assert_relative_eq!(layer.weights.as_slice(), [-0.6255188, 0.67383957, 0.8181262, 0.26284897].as_ref());
+        }
+    }
+}
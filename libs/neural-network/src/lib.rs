@@ -1,215 +1,895 @@
-use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
-pub struct Network {
-    layers: Vec<Layer>,
-}
+pub use self::{
+    activation::*, ctrnn::*, distance::*, error::*, fixed::*, initializer::*, neat::*,
+    persistence::*, plastic::*, quantized::*, render::*, scalar::*, trace::*, training::*,
+};
+use self::{graph::*, layer::*};
+
+mod activation;
+mod ctrnn;
+mod distance;
+mod error;
+mod fixed;
+mod graph;
+mod initializer;
+mod layer;
+mod mask;
+mod neat;
+mod persistence;
+mod plastic;
+mod quantized;
+mod render;
+mod scalar;
+mod trace;
+mod training;
 
+/// Layered neural network, computing with `T` - `f32` by default, see
+/// `Scalar` for the alternatives.
 #[derive(Clone, Debug)]
-struct Layer {
-    neurons: Vec<Neuron>,
+pub struct Network<T = f32> {
+    // Widths of the input groups; inputs are laid out group after group
+    inputs: Vec<usize>,
+    layers: Vec<Layer<T>>,
 }
 
-// represents a single layer's topology for initializing the network
-#[derive(Clone, Debug)]
+// Widths of the input groups, then sources and number of inputs of every
+// other layer
+type Wiring = (Vec<usize>, Vec<(Vec<usize>, usize)>);
+
+// represents a single layer's topology for initializing the network;
+// the activation and kind of the first (input) layer are never applied
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct LayerTopology {
     pub neurons: usize,
+    pub activation: Activation,
+    pub kind: LayerKind,
+
+    /// Which earlier layers (by their index in the topology) feed this one,
+    /// with their outputs concatenated in the given order - e.g. `[0, 2]`
+    /// for a skip connection from the inputs.
+    ///
+    /// `None` stands for just the previous layer, while an empty list makes
+    /// this layer another group of the network's inputs (such groups must
+    /// come before all of the other layers).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sources: Option<Vec<usize>>,
 }
 
-#[derive(Clone, Debug)]
-struct Neuron {
-    weights: Vec<f32>,
-    bias: f32,
+/// How a layer's neurons are wired.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LayerKind {
+    /// Each neuron sees the previous layer's outputs only
+    #[default]
+    Dense,
+
+    /// Elman-style recurrent layer - each neuron additionally sees this
+    /// layer's own outputs from the previous `propagate()` call
+    Elman,
+
+    /// Gated recurrent unit; like `Elman`, but with update and reset gates
+    /// deciding how much of the previous state to keep
+    Gru,
+
+    /// Circular 1D convolution - treats the inputs as a ring of cells (like
+    /// the ones an eye sees) and slides filters of `kernel` weights (plus a
+    /// bias) around it, so that a pattern is recognized no matter where on
+    /// the ring it shows up.
+    ///
+    /// Each filter yields one output per input, filter after filter, so
+    /// the layer's number of neurons must be a multiple of its inputs.
+    CircularConv { kernel: usize },
 }
 
-impl Network {
-    pub fn random(rng: &mut dyn rand::RngCore, layers: &[LayerTopology]) -> Self {
-        assert!(layers.len() > 1);
-        let layers = layers
-            .windows(2)
-            .map(|layer| Layer::random(rng, layer[0].neurons, layer[1].neurons))
-            .collect();
-        Self { layers }
+impl LayerKind {
+    pub fn is_recurrent(self) -> bool {
+        matches!(self, Self::Elman | Self::Gru)
     }
 
-    pub fn propagate(&self, inputs: Vec<f32>) -> Vec<f32> {
-        self.layers
-            .iter()
-            .fold(inputs, |inputs, layer| layer.propagate(inputs))
+    /// How many sets of weights each neuron of this kind has got
+    pub(crate) fn gates(self) -> usize {
+        match self {
+            Self::Dense | Self::Elman | Self::CircularConv { .. } => 1,
+            Self::Gru => 3,
+        }
+    }
+}
+
+impl LayerTopology {
+    pub fn new(neurons: usize) -> Self {
+        Self {
+            neurons,
+            activation: Activation::default(),
+            kind: LayerKind::default(),
+            sources: None,
+        }
     }
 
-    pub(crate) fn new(layers: Vec<Layer>) -> Self {
-        Self { layers }
+    /// Additional group of the network's inputs, see `sources`.
+    pub fn input(neurons: usize) -> Self {
+        Self::new(neurons).with_sources(Vec::new())
     }
 
-    pub fn weights(&self) -> Vec<f32> {
-        let mut weights = Vec::new();
+    pub fn with_activation(mut self, activation: Activation) -> Self {
+        self.activation = activation;
+        self
+    }
 
-        for layer in &self.layers {
-            for neuron in &layer.neurons {
-                weights.push(neuron.bias);
+    pub fn with_kind(mut self, kind: LayerKind) -> Self {
+        self.kind = kind;
+        self
+    }
 
-                for weight in &neuron.weights {
-                    weights.push(*weight);
+    pub fn with_sources(mut self, sources: impl Into<Vec<usize>>) -> Self {
+        self.sources = Some(sources.into());
+        self
+    }
+
+    // The first layer is always an input, no matter its sources
+    fn is_input(layers: &[LayerTopology], idx: usize) -> bool {
+        idx == 0 || layers[idx].sources.as_ref().is_some_and(Vec::is_empty)
+    }
+
+    fn sources(layers: &[LayerTopology], idx: usize) -> Vec<usize> {
+        layers[idx].sources.clone().unwrap_or_else(|| vec![idx - 1])
+    }
+
+    /// Number of values `Network::weights()` returns for the given
+    /// topology - that is: one bias plus one weight per input, per neuron
+    /// (recurrent neurons additionally have one weight per neuron of their
+    /// own layer, and GRU neurons have three such sets, one per gate).
+    ///
+    /// Inputs of layers with many sources are all of their sources'
+    /// neurons; input groups have got no weights at all. Convolutions only
+    /// have got a bias and `kernel` weights per filter.
+    pub fn weights_len(layers: &[LayerTopology]) -> usize {
+        (0..layers.len())
+            .filter(|&idx| !Self::is_input(layers, idx))
+            .map(|idx| {
+                let output = &layers[idx];
+                let recurrent = if output.kind.is_recurrent() { output.neurons } else { 0 };
+
+                let inputs: usize = Self::sources(layers, idx)
+                    .into_iter()
+                    .filter_map(|source| layers.get(source))
+                    .map(|source| source.neurons)
+                    .sum();
+
+                if let LayerKind::CircularConv { kernel } = output.kind {
+                    return (kernel + 1) * (output.neurons / inputs.max(1));
                 }
-            }
-        }
 
-        weights
+                output.kind.gates() * (inputs + recurrent + 1) * output.neurons
+            })
+            .sum()
     }
 
-    pub fn from_weights(layers: &[LayerTopology], mut weights: impl IntoIterator<Item = f32>) -> Self {
-        assert!(layers.len() > 1);
-        let mut weights = weights.into_iter();
-        let layers = layers
-            .windows(2)
-            .map(|layers| Layer::from_weights(layers[0].neurons, layers[1].neurons, &mut weights))
-            .collect();
-        if weights.next().is_some() {
-            panic!("got too many weights");
+    /// Checks the topology, returning widths of its input groups along with
+    /// the sources and number of inputs of every other layer.
+    fn wiring(layers: &[LayerTopology]) -> Result<Wiring, NetworkError> {
+        if layers.len() < 2 {
+            return Err(NetworkError::EmptyTopology);
+        }
+
+        if let Some(layer) = layers.iter().position(|layer| layer.neurons == 0) {
+            return Err(NetworkError::ZeroWidthLayer { layer });
+        }
+
+        let groups = (0..layers.len())
+            .take_while(|&idx| Self::is_input(layers, idx))
+            .count();
+
+        if groups == layers.len() {
+            return Err(NetworkError::EmptyTopology);
         }
-        Self { layers }
+
+        let inputs = layers[..groups].iter().map(|layer| layer.neurons).collect();
+
+        let wiring = (groups..layers.len())
+            .map(|layer| {
+                if Self::is_input(layers, layer) {
+                    return Err(NetworkError::MisplacedInputGroup { layer });
+                }
+
+                let sources = Self::sources(layers, layer);
+
+                if let Some(&source) = sources.iter().find(|&&source| source >= layer) {
+                    return Err(NetworkError::InvalidSource { layer, source });
+                }
+
+                let width = sources.iter().map(|&source| layers[source].neurons).sum();
+
+                if let LayerKind::CircularConv { kernel } = layers[layer].kind {
+                    if !(1..=width).contains(&kernel) || !layers[layer].neurons.is_multiple_of(width) {
+                        return Err(NetworkError::InvalidConvolution { layer });
+                    }
+                }
+
+                Ok((sources, width))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok((inputs, wiring))
     }
+}
+
+/// Reusable buffers for `Network::propagate_into()`; once they've grown to
+/// fit the widest layer, propagating doesn't allocate anymore.
+///
+/// Recurrent layers keep their state here as well, so that the network itself
+/// never changes - to carry the state from one step to the next one, keep
+/// propagating through the same `Scratch` (e.g. one per animal).
+#[derive(Clone, Debug, Default)]
+pub struct Scratch<T = f32> {
+    front: Vec<T>,
+    back: Vec<T>,
+
+    // Outputs of every input group and layer, for networks with skip
+    // connections
+    nodes: Vec<Vec<T>>,
 
+    // Previous outputs of every recurrent layer, one layer after another
+    state: Vec<T>,
+
+    // Inputs of the current layer, as quantized by `QuantizedNetwork`
+    quantized: Vec<i16>,
 }
 
-impl Layer {
-    pub fn random(rng: &mut dyn rand::RngCore, input_neurons: usize, output_neurons: usize) -> Self {
-        let neurons = (0..output_neurons)
-            .map(|_| Neuron::random(rng, input_neurons))
-            .collect();
-        Self { neurons }
+impl<T: Scalar> Scratch<T> {
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    fn propagate(&self, inputs: Vec<f32>) -> Vec<f32> {
-        self.neurons
-            .iter()
-            .map(|neuron| neuron.propagate(&inputs))
-            .collect()
+    /// Forgets everything recurrent layers have remembered so far, as if the
+    /// network has just been created.
+    pub fn reset_state(&mut self) {
+        self.state.clear();
     }
+}
 
-    pub fn new(neurons: Vec<Neuron>) -> Self {
-        assert!(!neurons.is_empty());
+// Random weights are always drawn as `f32` - for other scalars, `cast()`
+// the network afterwards
+impl Network {
+    pub fn random(rng: &mut dyn rand::RngCore, layers: &[LayerTopology]) -> Self {
+        Self::random_with(rng, layers, Initializer::default())
+    }
 
-        assert!(neurons
-            .iter()
-            .all(|neuron| neuron.weights.len() == neurons[0].weights.len()));
+    pub fn try_random(rng: &mut dyn rand::RngCore, layers: &[LayerTopology]) -> Result<Self, NetworkError> {
+        Self::try_random_with(rng, layers, Initializer::default())
+    }
 
-        Self { neurons }
+    /// Like `random()`, but draws weights and biases using given initializer.
+    pub fn random_with(
+        rng: &mut dyn rand::RngCore,
+        layers: &[LayerTopology],
+        initializer: Initializer,
+    ) -> Self {
+        Self::try_random_with(rng, layers, initializer).unwrap_or_else(|err| panic!("{}", err))
     }
 
-    pub fn from_weights( input_neurons: usize, output_neurons: usize, weights: &mut dyn Iterator<Item = f32>,) -> Self {
-        let neurons = (0..output_neurons)
-            .map(|_| Neuron::from_weights(input_neurons, weights))
+    pub fn try_random_with(
+        rng: &mut dyn rand::RngCore,
+        layers: &[LayerTopology],
+        initializer: Initializer,
+    ) -> Result<Self, NetworkError> {
+        if !initializer.is_valid() {
+            return Err(NetworkError::InvalidInitializer { initializer });
+        }
+
+        let (inputs, wiring) = LayerTopology::wiring(layers)?;
+
+        let layers = layers[inputs.len()..]
+            .iter()
+            .zip(wiring)
+            .map(|(layer, (sources, width))| {
+                Layer::random(rng, width, layer, initializer).with_sources(sources)
+            })
             .collect();
-        Self { neurons }
+        Ok(Self { inputs, layers })
     }
 }
 
-impl Neuron {
-    pub fn random(rng: &mut dyn rand::RngCore, output_neurons: usize) -> Self {
-        Self {
-            weights: (0..output_neurons)
-                .map(|_| rng.gen_range(-1.0..=1.0))
-                .collect(),
-            bias: rng.gen_range(-1.0..=1.0),
+impl<T: Scalar> Network<T> {
+    /// Propagates the inputs through the network.
+    ///
+    /// Recurrent layers (if there are any) start from a blank state on every
+    /// call - to have them remember previous inputs, see `propagate_into()`.
+    pub fn propagate(&self, inputs: Vec<T>) -> Vec<T> {
+        if !self.is_chain() {
+            return self.propagate_graph_into(&inputs, &mut Scratch::new()).to_vec();
+        }
+
+        // Layers take turns reading one buffer and writing into the other one,
+        // starting with the inputs' - so that their allocation gets reused,
+        // and the outputs don't have to be copied at the end
+        let mut scratch = Scratch { back: inputs, ..Scratch::new() };
+        let Scratch { front, back, state, .. } = &mut scratch;
+        let mut state = self.prepare_state(state);
+
+        for layer in &self.layers {
+            layer.propagate_step(back, layer.take_state(&mut state), front);
+            std::mem::swap(front, back);
         }
+
+        scratch.back
     }
 
-    fn propagate(&self, inputs: &[f32]) -> f32 {
-        // represent input into a node
-        assert_eq!(inputs.len(), self.weights.len());
-        let output = inputs
-            .iter()
-            .zip(&self.weights)
-            .map(|(input, weight)| input * weight)
-            .sum::<f32>();
-        // adding bias and doing relu activation function
-        (self.bias + output).max(0.0)
+    /// Like `propagate_into()`, but returns every layer's values (before and
+    /// after applying its activation) instead of just the outputs - handy
+    /// for figuring out what the network is doing.
+    ///
+    /// Traces are meant to be looked at, so they're always kept as `f32`.
+    pub fn propagate_traced(&self, inputs: Vec<T>, scratch: &mut Scratch<T>) -> Trace {
+        let layers = self.propagate_graph_traced(&inputs, &mut scratch.state);
+
+        Trace {
+            inputs: inputs.into_iter().map(Scalar::to_f32).collect(),
+            layers,
+        }
     }
 
-    pub fn new(bias: f32, weights: Vec<f32>) -> Self {
-        assert!(!weights.is_empty());
+    /// Allocation-free version of `propagate()` - layers write their outputs
+    /// into `scratch` (swapping between its two buffers), and the returned
+    /// slice borrows the last layer's outputs from there.
+    ///
+    /// Recurrent layers pick up the state left in `scratch` by the previous
+    /// call, and advance it.
+    pub fn propagate_into<'a>(&self, inputs: &[T], scratch: &'a mut Scratch<T>) -> &'a [T] {
+        if !self.is_chain() {
+            return self.propagate_graph_into(inputs, scratch);
+        }
+
+        let Scratch { front, back, state, .. } = scratch;
+        let (first, rest) = self.layers.split_first().expect("network has no layers");
+        let mut state = self.prepare_state(state);
 
-        Self { bias, weights }
+        first.propagate_step(inputs, first.take_state(&mut state), front);
+
+        for layer in rest {
+            layer.propagate_step(front, layer.take_state(&mut state), back);
+            std::mem::swap(front, back);
+        }
+
+        front
     }
 
-    pub fn from_weights(
-        output_neurons: usize,
-        weights: &mut dyn Iterator<Item = f32>,
-    ) -> Self {
-        let bias = weights.next().expect("got not enough weights");
+    // Makes room for the recurrent layers' state, starting from zeros if the
+    // one that's there doesn't fit this network
+    pub(crate) fn prepare_state<'a>(&self, state: &'a mut Vec<T>) -> &'a mut [T] {
+        let len = self.layers.iter().map(Layer::state_len).sum();
+
+        if state.len() != len {
+            state.clear();
+            state.resize(len, T::ZERO);
+        }
+
+        state
+    }
+
+    pub fn is_recurrent(&self) -> bool {
+        self.layers.iter().any(|layer| layer.kind.is_recurrent())
+    }
+
+    /// Evaluates many input vectors at once, e.g. a whole set of recorded
+    /// vision frames.
+    ///
+    /// `inputs` holds `batch` input vectors laid one after another, and so
+    /// does the result with the output vectors.
+    ///
+    /// Recurrent layers see each of the inputs with a blank state - i.e. the
+    /// batch is not treated as a sequence.
+    pub fn propagate_batch(&self, inputs: &[T], batch: usize) -> Vec<T> {
+        self.propagate_batch_into(inputs, batch, &mut Scratch::new()).to_vec()
+    }
+
+    /// Allocation-free version of `propagate_batch()`; recurrent layers see
+    /// each of the inputs with the state left in `scratch`, which is not
+    /// advanced.
+    pub fn propagate_batch_into<'a>(
+        &self,
+        inputs: &[T],
+        batch: usize,
+        scratch: &'a mut Scratch<T>,
+    ) -> &'a [T] {
+        if !self.is_chain() {
+            return self.propagate_graph_batch_into(inputs, batch, scratch);
+        }
+
+        let Scratch { front, back, state, .. } = scratch;
+        let (first, rest) = self.layers.split_first().expect("network has no layers");
+        let mut state = self.prepare_state(state);
+
+        first.propagate_batch_into(inputs, batch, first.take_state(&mut state), front);
+
+        for layer in rest {
+            layer.propagate_batch_into(front, batch, layer.take_state(&mut state), back);
+            std::mem::swap(front, back);
+        }
+
+        front
+    }
+
+    /// Reconstructs the topology this network was created with; since the
+    /// input layer's activation and kind are never applied, they're reported
+    /// as default (and so are sources of layers fed just by the previous
+    /// one).
+    pub fn topology(&self) -> Vec<LayerTopology> {
+        let groups = self.inputs.len();
+
+        let inputs = self.inputs.iter().enumerate().map(|(idx, &neurons)| {
+            if idx == 0 {
+                LayerTopology::new(neurons)
+            } else {
+                LayerTopology::input(neurons)
+            }
+        });
 
-        let weights = (0..output_neurons)
-            .map(|_| weights.next().expect("got not enough weights"))
+        let layers = self.layers.iter().enumerate().map(|(idx, layer)| LayerTopology {
+            neurons: layer.outputs(),
+            activation: layer.activation.clone(),
+            kind: layer.kind,
+            sources: if layer.sources == [groups + idx - 1] {
+                None
+            } else {
+                Some(layer.sources.clone())
+            },
+        });
+
+        inputs.chain(layers).collect()
+    }
+
+    // Chains given layers one after another
+    #[cfg(test)]
+    pub(crate) fn new(layers: Vec<Layer<T>>) -> Self {
+        let layers: Vec<_> = layers
+            .into_iter()
+            .enumerate()
+            .map(|(idx, layer)| layer.with_sources(vec![idx]))
             .collect();
 
-        Self { bias, weights }
+        Self { inputs: vec![layers[0].inputs], layers }
     }
 
-}
+    pub fn weights(&self) -> Vec<T> {
+        let mut weights = Vec::new();
+
+        for layer in &self.layers {
+            layer.extend_weights(&mut weights);
+        }
+
+        weights
+    }
 
+    pub fn from_weights(layers: &[LayerTopology], weights: impl IntoIterator<Item = T>) -> Self {
+        Self::try_from_weights(layers, weights).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_from_weights(
+        layers: &[LayerTopology],
+        weights: impl IntoIterator<Item = T>,
+    ) -> Result<Self, NetworkError> {
+        let (inputs, wiring) = LayerTopology::wiring(layers)?;
+
+        let weights: Vec<T> = weights.into_iter().collect();
+        let expected = LayerTopology::weights_len(layers);
+
+        if weights.len() < expected {
+            return Err(NetworkError::NotEnoughWeights { expected, found: weights.len() });
+        }
+
+        if weights.len() > expected {
+            return Err(NetworkError::TooManyWeights { expected, found: weights.len() });
+        }
+
+        if let Some(index) = weights.iter().position(|weight| !weight.is_finite()) {
+            return Err(NetworkError::NonFiniteWeight { index, value: weights[index].to_f32() });
+        }
+
+        let mut weights = weights.into_iter();
+        let layers = layers[inputs.len()..]
+            .iter()
+            .zip(wiring)
+            .map(|(layer, (sources, width))| {
+                Layer::from_weights(width, layer, &mut weights).with_sources(sources)
+            })
+            .collect();
+        Ok(Self { inputs, layers })
+    }
+
+    /// Like `try_from_weights()`, but additionally disables connections
+    /// according to given mask (if any) - e.g. the one of a chromosome, see
+    /// `set_mask()`.
+    pub fn try_from_masked_weights(
+        layers: &[LayerTopology],
+        weights: impl IntoIterator<Item = T>,
+        mask: Option<&[bool]>,
+    ) -> Result<Self, NetworkError> {
+        let mut network = Self::try_from_weights(layers, weights)?;
+
+        if let Some(mask) = mask {
+            network.set_mask(mask.iter().copied())?;
+        }
+
+        Ok(network)
+    }
+
+    /// Converts this network to compute with another scalar type, e.g. to
+    /// evaluate an evolved `Network<f32>` as a bit-reproducible
+    /// `Network<Fixed>`.
+    pub fn cast<U: Scalar>(&self) -> Network<U> {
+        let convert = |values: &[T]| values.iter().map(|value| U::from_f64(value.to_f64())).collect();
+
+        Network {
+            inputs: self.inputs.clone(),
+            layers: self
+                .layers
+                .iter()
+                .map(|layer| Layer {
+                    inputs: layer.inputs,
+                    sources: layer.sources.clone(),
+                    neurons: layer.neurons,
+                    kind: layer.kind,
+                    biases: convert(&layer.biases),
+                    weights: convert(&layer.weights),
+                    mask: layer.mask.clone(),
+                    active: layer.active.clone(),
+                    activation: layer.activation.clone(),
+                })
+                .collect(),
+        }
+    }
+
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    mod neuron {
+    mod propagate {
         use super::*;
 
-        mod test_neuron_propagate {
-            use super::*;
+        #[test]
+        fn respects_layer_activations() {
+            let layers = [
+                LayerTopology::new(2),
+                LayerTopology::new(1),
+                LayerTopology::new(1).with_activation(Activation::Tanh),
+            ];
 
-            #[test]
-            fn test() {
-                let neuron = Neuron {
-                    bias: 0.5,
-                    weights: vec![-0.3, 0.8],
-                };
-            
-                // Ensures `.max()` (our ReLU) works:
-                approx::assert_relative_eq!(
-                    neuron.propagate(&[-10.0, -10.0]),
-                    0.0,
-                );
-            
-                // `0.5` and `1.0` chosen by a fair dice roll:
-                approx::assert_relative_eq!(
-                    neuron.propagate(&[0.5, 1.0]),
-                    (-0.3 * 0.5) + (0.8 * 1.0) + 0.5,
-                );
-            
+            let network = Network::from_weights(&layers, vec![0.5, 1.0, 1.0, -0.5, -1.0]);
+
+            // hidden: relu(0.5 + 1.0 + 1.0) = 2.5
+            // output: tanh(-0.5 + (-1.0 * 2.5)) = tanh(-3.0)
+            let actual = network.propagate(vec![1.0, 1.0]);
+            let expected = vec![(-3.0_f32).tanh()];
+
+            approx::assert_relative_eq!(
+                actual.as_slice(),
+                expected.as_slice(),
+            );
+        }
+    }
+
+    mod propagate_into {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use super::*;
+
+        #[test]
+        fn matches_propagate() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let layers = [
+                LayerTopology::new(3),
+                LayerTopology::new(6),
+                LayerTopology::new(2).with_activation(Activation::Tanh),
+            ];
+            let network = Network::random(&mut rng, &layers);
+            let mut scratch = Scratch::new();
+
+            // Using the same scratch twice ensures leftovers from previous
+            // calls don't leak into the results
+            for inputs in [[0.1, 0.5, 0.9], [0.7, 0.0, 0.3]] {
+                let expected = network.propagate(inputs.to_vec());
+                let actual = network.propagate_into(&inputs, &mut scratch);
+
+                approx::assert_relative_eq!(actual, expected.as_slice());
             }
         }
+    }
 
-        // due to the random neuron initialization, this test is not deterministic and the numbers need to be reproduced by first running the test 
-        // and failing it the plugging in those numbers
-        mod test_neuron_random {
-            use rand::SeedableRng;
-            use rand_chacha::ChaCha8Rng;
+    mod propagate_traced {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
 
-            use super::*;
+        use super::*;
 
-            #[test]
-            fn test() {
+        #[test]
+        fn test() {
+            let layers = [
+                LayerTopology::new(2),
+                LayerTopology::new(2),
+                LayerTopology::new(1).with_activation(Activation::Tanh),
+            ];
+
+            // Hidden neurons: 0.5 + x0 - x1 and -0.5 - x0 + x1; output: 0.1 +
+            // 2.0 * h0 + 3.0 * h1
+            let network = Network::from_weights(
+                &layers,
+                vec![0.5, 1.0, -1.0, -0.5, -1.0, 1.0, 0.1, 2.0, 3.0],
+            );
+
+            let trace = network.propagate_traced(vec![1.0, 0.25], &mut Scratch::new());
+
+            assert_eq!(trace.inputs, vec![1.0, 0.25]);
+            assert_eq!(trace.layers.len(), 2);
+
+            let hidden = &trace.layers[0];
+            approx::assert_relative_eq!(hidden.pre_activation.as_slice(), [1.25, -1.25].as_ref());
+            approx::assert_relative_eq!(hidden.post_activation.as_slice(), [1.25, 0.0].as_ref());
+
+            let output = &trace.layers[1];
+            let expected = [2.6_f32.tanh()];
+            approx::assert_relative_eq!(output.pre_activation.as_slice(), [2.6].as_ref());
+            approx::assert_relative_eq!(trace.outputs(), expected.as_ref());
+        }
+
+        #[test]
+        fn matches_propagate() {
+            for kind in [LayerKind::Dense, LayerKind::Elman, LayerKind::Gru] {
                 let mut rng = ChaCha8Rng::from_seed(Default::default());
-                let neuron = Neuron::random(&mut rng, 4);
+                let layers = [
+                    LayerTopology::new(3),
+                    LayerTopology::new(4).with_kind(kind),
+                    LayerTopology::new(2).with_activation(Activation::Tanh),
+                ];
+                let network = Network::random(&mut rng, &layers);
+                let (mut scratch, mut traced) = (Scratch::new(), Scratch::new());
 
-                approx::assert_relative_eq!(neuron.bias, 0.5238807);
+                // Twice, so that recurrent layers get to use their state
+                for inputs in [[0.1, 0.5, 0.9], [0.7, 0.0, 0.3]] {
+                    let expected = network.propagate_into(&inputs, &mut scratch).to_vec();
+                    let trace = network.propagate_traced(inputs.to_vec(), &mut traced);
 
-                approx::assert_relative_eq!(neuron.weights.as_slice(), [-0.6255188, 0.67383957, 0.8181262, 0.26284897].as_ref());
+                    approx::assert_relative_eq!(trace.outputs(), expected.as_slice());
+                }
             }
         }
     }
 
+    mod propagate_batch {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use super::*;
+
+        #[test]
+        fn matches_propagate() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let layers = [
+                LayerTopology::new(3),
+                LayerTopology::new(6),
+                LayerTopology::new(2).with_activation(Activation::Tanh),
+            ];
+            let network = Network::random(&mut rng, &layers);
+            let inputs = [0.1, 0.5, 0.9, 0.7, 0.0, 0.3, -0.2, 0.4, 1.0];
+
+            let expected: Vec<f32> = inputs
+                .chunks(3)
+                .flat_map(|inputs| network.propagate(inputs.to_vec()))
+                .collect();
+
+            let actual = network.propagate_batch(&inputs, 3);
+
+            approx::assert_relative_eq!(actual.as_slice(), expected.as_slice());
+        }
+
+        #[test]
+        #[should_panic]
+        fn rejects_mismatched_batch() {
+            let network = Network::from_weights(
+                &[LayerTopology::new(2), LayerTopology::new(1)],
+                vec![0.1, 0.2, 0.3],
+            );
+
+            network.propagate_batch(&[1.0, 2.0, 3.0], 2);
+        }
+    }
+
+    mod recurrent {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use super::*;
+
+        fn layers(kind: LayerKind) -> [LayerTopology; 3] {
+            [
+                LayerTopology::new(3),
+                LayerTopology::new(4).with_activation(Activation::Tanh).with_kind(kind),
+                LayerTopology::new(2).with_activation(Activation::Tanh),
+            ]
+        }
+
+        #[test]
+        fn remembers_previous_inputs_until_reset() {
+            for kind in [LayerKind::Elman, LayerKind::Gru] {
+                let mut rng = ChaCha8Rng::from_seed(Default::default());
+                let network = Network::random(&mut rng, &layers(kind));
+                let mut scratch = Scratch::new();
+
+                let first = network.propagate_into(&[0.1, 0.5, 0.9], &mut scratch).to_vec();
+                let second = network.propagate_into(&[0.1, 0.5, 0.9], &mut scratch).to_vec();
+
+                assert!(network.is_recurrent());
+                assert_ne!(first, second, "{:?} forgot the previous input", kind);
+
+                // `propagate()` always starts from scratch
+                assert_eq!(network.propagate(vec![0.1, 0.5, 0.9]), first);
+
+                scratch.reset_state();
+
+                assert_eq!(network.propagate_into(&[0.1, 0.5, 0.9], &mut scratch), first);
+            }
+        }
+
+        #[test]
+        fn weights_roundtrip() {
+            for kind in [LayerKind::Elman, LayerKind::Gru] {
+                let mut rng = ChaCha8Rng::from_seed(Default::default());
+                let network = Network::random(&mut rng, &layers(kind));
+                let weights = network.weights();
+
+                assert_eq!(weights.len(), LayerTopology::weights_len(&layers(kind)));
+
+                let actual = Network::from_weights(&layers(kind), weights.clone()).weights();
+
+                approx::assert_relative_eq!(actual.as_slice(), weights.as_slice());
+            }
+        }
+    }
+
+    mod random_with {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use super::*;
+
+        fn layers() -> [LayerTopology; 3] {
+            [LayerTopology::new(3), LayerTopology::new(5), LayerTopology::new(2)]
+        }
+
+        #[test]
+        fn is_reproducible() {
+            for initializer in [Initializer::Xavier, Initializer::He, Initializer::Normal(0.1)] {
+                let a = Network::random_with(&mut ChaCha8Rng::from_seed(Default::default()), &layers(), initializer);
+                let b = Network::random_with(&mut ChaCha8Rng::from_seed(Default::default()), &layers(), initializer);
+
+                assert_eq!(a.weights(), b.weights());
+            }
+        }
+
+        #[test]
+        fn defaults_to_uniform() {
+            let a = Network::random(&mut ChaCha8Rng::from_seed(Default::default()), &layers());
+            let b = Network::random_with(
+                &mut ChaCha8Rng::from_seed(Default::default()),
+                &layers(),
+                Initializer::Uniform(1.0),
+            );
+
+            assert_eq!(a.weights(), b.weights());
+        }
+
+        #[test]
+        fn scales_by_fan_in_and_fan_out() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let network = Network::random_with(&mut rng, &layers(), Initializer::Xavier);
+
+            // sqrt(6 / (3 + 5)) and sqrt(6 / (5 + 2))
+            let limits = [0.8660254, 0.9258201];
+
+            for (layer, limit) in network.layers.iter().zip(limits) {
+                assert!(layer.weights.iter().chain(&layer.biases).all(|w| w.abs() <= limit));
+            }
+        }
+
+        #[test]
+        fn zeros() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let network = Network::random_with(&mut rng, &layers(), Initializer::Zeros);
+
+            assert!(network.weights().iter().all(|&w| w == 0.0));
+        }
+    }
+
+    mod try_random {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use super::*;
+
+        #[test]
+        fn rejects_single_layer() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+            assert_eq!(
+                Network::try_random(&mut rng, &[LayerTopology::new(3)]).unwrap_err(),
+                NetworkError::EmptyTopology,
+            );
+        }
+
+        #[test]
+        fn rejects_zero_width_layer() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let layers = [LayerTopology::new(3), LayerTopology::new(0), LayerTopology::new(2)];
+
+            assert_eq!(
+                Network::try_random(&mut rng, &layers).unwrap_err(),
+                NetworkError::ZeroWidthLayer { layer: 1 },
+            );
+        }
+
+        #[test]
+        fn rejects_invalid_initializer() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let layers = [LayerTopology::new(3), LayerTopology::new(2)];
+
+            assert_eq!(
+                Network::try_random_with(&mut rng, &layers, Initializer::Uniform(-1.0)).unwrap_err(),
+                NetworkError::InvalidInitializer { initializer: Initializer::Uniform(-1.0) },
+            );
+        }
+    }
+
+    mod try_from_weights {
+        use super::*;
+
+        fn layers() -> [LayerTopology; 2] {
+            [LayerTopology::new(3), LayerTopology::new(2)]
+        }
+
+        #[test]
+        fn rejects_not_enough_weights() {
+            assert_eq!(
+                Network::try_from_weights(&layers(), vec![0.1; 7]).unwrap_err(),
+                NetworkError::NotEnoughWeights { expected: 8, found: 7 },
+            );
+        }
+
+        #[test]
+        fn rejects_too_many_weights() {
+            assert_eq!(
+                Network::try_from_weights(&layers(), vec![0.1; 9]).unwrap_err(),
+                NetworkError::TooManyWeights { expected: 8, found: 9 },
+            );
+        }
+
+        #[test]
+        fn rejects_non_finite_weights() {
+            let mut weights = vec![0.1; 8];
+            weights[5] = f32::INFINITY;
+
+            assert_eq!(
+                Network::try_from_weights(&layers(), weights).unwrap_err(),
+                NetworkError::NonFiniteWeight { index: 5, value: f32::INFINITY },
+            );
+        }
+
+        #[test]
+        #[should_panic(expected = "got too many weights")]
+        fn from_weights_still_panics() {
+            Network::from_weights(&layers(), vec![0.1; 9]);
+        }
+    }
+
     mod weights {
         use super::*;
 
         #[test]
         fn test() {
             let network = Network::new(vec![
-                Layer::new(vec![Neuron::new(0.1, vec![0.2, 0.3, 0.4])]),
-                Layer::new(vec![Neuron::new(0.5, vec![0.6, 0.7, 0.8])]),
+                Layer::new(vec![(0.1, vec![0.2, 0.3, 0.4])], Activation::ReLU),
+                Layer::new(vec![(0.5, vec![0.6, 0.7, 0.8])], Activation::ReLU),
             ]);
 
             let actual = network.weights();
@@ -225,14 +905,14 @@ mod tests {
     
             #[test]
             fn test() {
-                let layers = &[
-                    LayerTopology { neurons: 3 },
-                    LayerTopology { neurons: 2 },
+                let layers = [
+                    LayerTopology::new(3),
+                    LayerTopology::new(2),
                 ];
     
                 let weights = vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8];
     
-                let network = Network::from_weights(layers, weights.clone());
+                let network = Network::from_weights(&layers, weights.clone());
                 let actual: Vec<_> = network.weights();
     
                 approx::assert_relative_eq!(
@@ -243,4 +923,226 @@ mod tests {
         }
     }
 
+    mod skip_connections {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use super::*;
+
+        // Two input groups (vision and, say, energy); the hidden layer sees
+        // vision only, while the output sees everything
+        fn layers() -> Vec<LayerTopology> {
+            vec![
+                LayerTopology::new(2),
+                LayerTopology::input(1),
+                LayerTopology::new(2).with_sources([0]),
+                LayerTopology::new(1)
+                    .with_activation(Activation::Identity)
+                    .with_sources([0, 1, 2]),
+            ]
+        }
+
+        #[test]
+        fn propagate() {
+            // hidden: relu(0.5 + x0 - x1), relu(-x0 + 2.0 * x1)
+            // output: 0.1 + x0 + 2.0 * x1 + 3.0 * e + h0 - h1
+            let network = Network::from_weights(
+                &layers(),
+                vec![0.5, 1.0, -1.0, 0.0, -1.0, 2.0, 0.1, 1.0, 2.0, 3.0, 1.0, -1.0],
+            );
+
+            // hidden: 0.0 and 1.5
+            let actual = network.propagate(vec![0.5, 1.0, 2.0]);
+            let expected = [0.1 + 0.5 + 2.0 + 6.0 - 1.5];
+
+            approx::assert_relative_eq!(actual.as_slice(), expected.as_ref());
+        }
+
+        #[test]
+        fn propagation_methods_agree() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut layers = layers();
+            layers[2] = layers[2].clone().with_kind(LayerKind::Elman);
+
+            let network = Network::random(&mut rng, &layers);
+            let (mut scratch, mut traced) = (Scratch::new(), Scratch::new());
+
+            let batch = [0.1, 0.5, 0.9, 0.7, 0.0, 0.3];
+            let expected = network.propagate_batch(&batch, 2);
+
+            for (inputs, expected) in batch.chunks(3).zip(expected.chunks(1)) {
+                let actual = network.propagate_into(inputs, &mut scratch).to_vec();
+                let trace = network.propagate_traced(inputs.to_vec(), &mut traced);
+
+                approx::assert_relative_eq!(trace.outputs(), actual.as_slice());
+
+                scratch.reset_state();
+                traced.reset_state();
+
+                approx::assert_relative_eq!(actual.as_slice(), expected);
+            }
+        }
+
+        #[test]
+        fn topology() {
+            let network = Network::from_weights(&layers(), vec![0.0; LayerTopology::weights_len(&layers())]);
+
+            assert_eq!(LayerTopology::weights_len(&layers()), 12);
+            assert_eq!(network.topology(), layers());
+        }
+
+        #[test]
+        fn rejects_sources_from_later_layers() {
+            let layers = [
+                LayerTopology::new(1),
+                LayerTopology::new(1).with_sources([2]),
+                LayerTopology::new(1),
+            ];
+
+            assert_eq!(
+                Network::try_from_weights(&layers, vec![0.0; 4]).unwrap_err(),
+                NetworkError::InvalidSource { layer: 1, source: 2 },
+            );
+        }
+
+        #[test]
+        fn rejects_misplaced_input_groups() {
+            let layers = [
+                LayerTopology::new(1),
+                LayerTopology::new(1),
+                LayerTopology::input(1),
+                LayerTopology::new(1),
+            ];
+
+            assert_eq!(
+                Network::try_from_weights(&layers, vec![0.0; 4]).unwrap_err(),
+                NetworkError::MisplacedInputGroup { layer: 2 },
+            );
+        }
+    }
+
+    mod convolution {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use super::*;
+
+        fn layers(kernel: usize, neurons: usize) -> Vec<LayerTopology> {
+            vec![
+                LayerTopology::new(4),
+                LayerTopology::new(neurons).with_kind(LayerKind::CircularConv { kernel }),
+                LayerTopology::new(2).with_activation(Activation::Tanh),
+            ]
+        }
+
+        #[test]
+        fn weights_len() {
+            // 2 filters of (3 + 1), then 2 x (8 + 1)
+            assert_eq!(LayerTopology::weights_len(&layers(3, 8)), 8 + 18);
+        }
+
+        #[test]
+        fn propagation_methods_agree() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let network = Network::random(&mut rng, &layers(3, 8));
+
+            let batch = [0.1, 0.5, 0.9, 0.2, 0.7, 0.0, 0.3, 0.4];
+            let expected = network.propagate_batch(&batch, 2);
+
+            for (inputs, expected) in batch.chunks(4).zip(expected.chunks(2)) {
+                let actual = network.propagate(inputs.to_vec());
+                let trace = network.propagate_traced(inputs.to_vec(), &mut Scratch::new());
+
+                approx::assert_relative_eq!(actual.as_slice(), expected);
+                approx::assert_relative_eq!(trace.outputs(), expected);
+            }
+        }
+
+        #[test]
+        fn rejects_mismatched_kernels() {
+            for (kernel, neurons) in [(0, 8), (5, 8), (3, 6)] {
+                let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+                assert_eq!(
+                    Network::try_random(&mut rng, &layers(kernel, neurons)).unwrap_err(),
+                    NetworkError::InvalidConvolution { layer: 1 },
+                );
+            }
+        }
+    }
+
+    mod cast {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use super::*;
+
+        fn network() -> Network {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+            Network::random(
+                &mut rng,
+                &[
+                    LayerTopology::new(3),
+                    LayerTopology::new(4).with_kind(LayerKind::Gru),
+                    LayerTopology::new(2).with_activation(Activation::Tanh),
+                ],
+            )
+        }
+
+        #[test]
+        fn f64_matches_f32() {
+            let network = network();
+            let wide = network.cast::<f64>();
+            let (mut scratch, mut wide_scratch) = (Scratch::new(), Scratch::new());
+
+            for inputs in [[0.1, 0.5, 0.9], [0.7, 0.0, 0.3]] {
+                let expected = network.propagate_into(&inputs, &mut scratch).to_vec();
+                let actual: Vec<_> = wide
+                    .propagate_into(&inputs.map(|x| x as f64), &mut wide_scratch)
+                    .iter()
+                    .map(|&x| x as f32)
+                    .collect();
+
+                approx::assert_relative_eq!(actual.as_slice(), expected.as_slice(), epsilon = 1e-6);
+            }
+        }
+
+        #[test]
+        fn fixed_matches_f32() {
+            let network = network();
+            let fixed = network.cast::<Fixed>();
+            let (mut scratch, mut fixed_scratch) = (Scratch::new(), Scratch::new());
+
+            for inputs in [[0.1, 0.5, 0.9], [0.7, 0.0, 0.3]] {
+                let expected = network.propagate_into(&inputs, &mut scratch).to_vec();
+                let actual: Vec<_> = fixed
+                    .propagate_into(&inputs.map(Fixed::from_f32), &mut fixed_scratch)
+                    .iter()
+                    .map(|x| x.to_f32())
+                    .collect();
+
+                approx::assert_relative_eq!(actual.as_slice(), expected.as_slice(), epsilon = 1e-3);
+            }
+        }
+
+        // Pins the exact bits, so that any platform-dependent arithmetic
+        // sneaking into `Fixed` gets noticed
+        #[test]
+        fn fixed_is_bit_exact() {
+            let network = network().cast::<Fixed>();
+            let inputs: Vec<_> = [0.1, 0.5, 0.9].iter().map(|&x| Fixed::from_f32(x)).collect();
+            let mut scratch = Scratch::new();
+
+            network.propagate_into(&inputs, &mut scratch);
+
+            let actual: Vec<_> = network
+                .propagate_into(&inputs, &mut scratch)
+                .iter()
+                .map(|x| x.to_bits())
+                .collect();
+
+            assert_eq!(actual, vec![-19937, 34670]);
+        }
+    }
 }
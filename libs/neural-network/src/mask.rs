@@ -0,0 +1,239 @@
+use crate::*;
+
+impl<T: Scalar> Network<T> {
+    /// Which connections are enabled, in `weights()` order; biases cannot be
+    /// disabled, so their entries are always `true`.
+    pub fn mask(&self) -> Vec<bool> {
+        let mut mask = Vec::new();
+
+        for layer in &self.layers {
+            layer.extend_mask(&mut mask);
+        }
+
+        mask
+    }
+
+    /// Enables and disables connections according to given mask (laid out
+    /// just like `mask()`); entries corresponding to biases are ignored.
+    ///
+    /// Disabled connections are skipped during propagation; their weights
+    /// get zeroed (so that training leaves them alone), and when enabled
+    /// back, they start over from zero.
+    ///
+    /// To build a network with a mask right away, see
+    /// `try_from_masked_weights()`.
+    pub fn set_mask(&mut self, mask: impl IntoIterator<Item = bool>) -> Result<(), NetworkError> {
+        let mask: Vec<bool> = mask.into_iter().collect();
+        let expected = LayerTopology::weights_len(&self.topology());
+
+        if mask.len() != expected {
+            return Err(NetworkError::MaskLength { expected, found: mask.len() });
+        }
+
+        let mut mask = mask.into_iter();
+
+        for layer in &mut self.layers {
+            layer.set_mask(&mut mask);
+        }
+
+        Ok(())
+    }
+
+    /// Disables every enabled connection whose weight is closer to zero
+    /// than `threshold`, returning how many of them got disabled.
+    pub fn prune(&mut self, threshold: T) -> usize {
+        let mut pruned = 0;
+
+        for layer in &mut self.layers {
+            for (weight, enabled) in layer.weights.iter_mut().zip(&mut layer.mask) {
+                if *enabled && weight.abs() < threshold {
+                    *weight = T::ZERO;
+                    *enabled = false;
+                    pruned += 1;
+                }
+            }
+
+            layer.update_active();
+        }
+
+        pruned
+    }
+
+    /// Number of parameters that actually take part in propagation - that
+    /// is: all of the biases plus weights of enabled connections.
+    pub fn active_params(&self) -> usize {
+        self.layers
+            .iter()
+            .map(|layer| {
+                layer.biases.len() + layer.mask.iter().filter(|&&enabled| enabled).count()
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2 inputs, 2 hidden neurons, 1 output - 9 parameters in total
+    fn network() -> Network {
+        Network::from_weights(
+            &[
+                LayerTopology::new(2),
+                LayerTopology::new(2).with_activation(Activation::Identity),
+                LayerTopology::new(1).with_activation(Activation::Identity),
+            ],
+            vec![0.1, 1.0, -0.05, 0.2, -2.0, 0.01, 0.0, 0.5, -0.5],
+        )
+    }
+
+    mod prune {
+        use super::*;
+
+        #[test]
+        fn test() {
+            let mut network = network();
+
+            assert_eq!(network.active_params(), 9);
+            assert_eq!(network.prune(0.1), 2);
+            assert_eq!(network.prune(0.1), 0);
+            assert_eq!(network.active_params(), 7);
+
+            assert_eq!(
+                network.mask(),
+                vec![true, true, false, true, true, false, true, true, true]
+            );
+
+            // Biases are never pruned, even if they're zero
+            let weights = network.weights();
+
+            approx::assert_relative_eq!(
+                weights.as_slice(),
+                [0.1, 1.0, 0.0, 0.2, -2.0, 0.0, 0.0, 0.5, -0.5].as_ref()
+            );
+        }
+
+        #[test]
+        fn skips_disabled_connections_in_propagation() {
+            let mut network = network();
+            let inputs = vec![1.0, 10.0];
+
+            // h0 = 0.1 + 1.0 - 0.5, h1 = 0.2 - 2.0 + 0.1
+            approx::assert_relative_eq!(network.propagate(inputs.clone())[0], 0.5 * 0.6 - 0.5 * -1.7);
+
+            network.prune(0.1);
+
+            approx::assert_relative_eq!(network.propagate(inputs)[0], 0.5 * 1.1 - 0.5 * -1.8);
+        }
+
+        #[test]
+        fn survives_training() {
+            let mut network = network();
+
+            network.prune(0.1);
+            network.train_epoch(&[1.0, 1.0], &[3.0], 1, &mut Sgd::new(0.1));
+
+            assert_eq!(network.active_params(), 7);
+            approx::assert_relative_eq!(network.weights()[2], 0.0);
+            approx::assert_relative_eq!(network.weights()[5], 0.0);
+        }
+    }
+
+    mod set_mask {
+        use super::*;
+
+        #[test]
+        fn test() {
+            let mut network = network();
+            let mask = vec![false, false, true, true, true, true, false, true, false];
+
+            network.set_mask(mask).unwrap();
+
+            // Bias bits are ignored
+            assert_eq!(
+                network.mask(),
+                vec![true, false, true, true, true, true, true, true, false]
+            );
+
+            assert_eq!(network.active_params(), 7);
+        }
+
+        #[test]
+        fn keeps_masked_weights_inert_after_from_weights() {
+            let mut weights = network().weights();
+            weights[2] = 100.0;
+
+            let mut mask = vec![true; 9];
+            mask[2] = false;
+
+            let network = Network::try_from_masked_weights(&network().topology(), weights, Some(&mask)).unwrap();
+            let mut expected = self::network();
+
+            expected.set_mask(mask.clone()).unwrap();
+
+            assert_eq!(network.mask(), mask);
+
+            approx::assert_relative_eq!(network.weights()[2], 0.0);
+
+            approx::assert_relative_eq!(
+                network.propagate(vec![1.0, 10.0])[0],
+                expected.propagate(vec![1.0, 10.0])[0]
+            );
+        }
+
+        #[test]
+        fn skips_disabled_connections() {
+            use rand::SeedableRng;
+            use rand_chacha::ChaCha8Rng;
+
+            let kinds = [
+                LayerKind::Dense,
+                LayerKind::Elman,
+                LayerKind::Gru,
+                LayerKind::CircularConv { kernel: 3 },
+            ];
+
+            for kind in kinds {
+                let mut rng = ChaCha8Rng::from_seed(Default::default());
+                let topology = [
+                    LayerTopology::new(4),
+                    LayerTopology::new(4).with_kind(kind).with_activation(Activation::Tanh),
+                ];
+
+                let mut network = Network::random(&mut rng, &topology);
+                let mask: Vec<bool> = (0..network.weights().len()).map(|idx| idx % 3 != 1).collect();
+
+                network.set_mask(mask).unwrap();
+
+                let inputs = vec![0.5, -1.0, 0.25, 1.0];
+                let expected = network.propagate_traced(inputs.clone(), &mut Scratch::new());
+
+                // Propagation mustn't even look at the disabled weights
+                for layer in &mut network.layers {
+                    for (weight, enabled) in layer.weights.iter_mut().zip(&layer.mask) {
+                        if !enabled {
+                            *weight = 100.0;
+                        }
+                    }
+                }
+
+                let actual = network.propagate_traced(inputs, &mut Scratch::new());
+
+                assert_eq!(actual, expected, "{:?}", kind);
+            }
+        }
+
+        #[test]
+        fn rejects_wrong_length() {
+            assert_eq!(
+                network().set_mask(vec![true; 8]).unwrap_err(),
+                NetworkError::MaskLength { expected: 9, found: 8 },
+            );
+
+            assert_eq!(
+                network().set_mask(vec![true; 10]).unwrap_err(),
+                NetworkError::MaskLength { expected: 9, found: 10 },
+            );
+        }
+    }
+}
@@ -0,0 +1,23 @@
+//! NEAT (NeuroEvolution of Augmenting Topologies) - instead of tuning
+//! weights of a network with fixed topology, genomes start out as tiny
+//! networks (inputs wired straight into outputs) and grow hidden neurons and
+//! connections through mutations.
+//!
+//! The moving parts are:
+//!
+//! - `NeatGenome`, a graph of node and connection genes,
+//! - `InnovationHistory`, which numbers structural mutations, so that genes
+//!   of different genomes can be lined up during crossover,
+//! - `NeatMutation`, which perturbs weights and adds nodes and connections,
+//! - `Speciation`, which groups similar genomes together, so that new
+//!   structures get a few generations to tune their weights before having
+//!   to compete with the rest of the population,
+//! - `NeatNetwork`, genome's phenotype, evaluated like `Network`.
+
+pub use self::{genome::*, innovation::*, mutation::*, network::*, speciation::*};
+
+mod genome;
+mod innovation;
+mod mutation;
+mod network;
+mod speciation;
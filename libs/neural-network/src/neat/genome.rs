@@ -0,0 +1,528 @@
+use rand::{Rng, RngCore};
+
+use crate::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeKind {
+    Input,
+    Output,
+    Hidden,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeGene {
+    pub id: usize,
+    pub kind: NodeKind,
+
+    /// Not applied to input nodes, which pass their values through as-is
+    pub bias: f32,
+    pub activation: Activation,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConnectionGene {
+    pub innovation: usize,
+    pub from: usize,
+    pub to: usize,
+    pub weight: f32,
+
+    /// Disabled connections don't take part in propagation, but are kept
+    /// around so that crossover can still line them up
+    pub enabled: bool,
+}
+
+/// Weights of the terms of `NeatGenome::distance()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompatibilityCoefficients {
+    pub excess: f32,
+    pub disjoint: f32,
+    pub weight: f32,
+}
+
+impl Default for CompatibilityCoefficients {
+    // Values used in the original NEAT paper
+    fn default() -> Self {
+        Self {
+            excess: 1.0,
+            disjoint: 1.0,
+            weight: 0.4,
+        }
+    }
+}
+
+/// Graph genome of a NEAT network.
+///
+/// Connections always point "forward" - mutations never introduce a cycle,
+/// and crossover only ever inherits the structure of a single parent - so
+/// every genome can be turned into a feed-forward `NeatNetwork`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NeatGenome {
+    // Sorted by id
+    pub(crate) nodes: Vec<NodeGene>,
+
+    // Sorted by innovation number
+    pub(crate) connections: Vec<ConnectionGene>,
+}
+
+impl NeatGenome {
+    /// Creates the smallest possible genome: every input connected straight
+    /// to every output, with random weights.
+    ///
+    /// Outputs use tanh, so that they're within <-1, 1> (just like outputs
+    /// of `Ctrnn`).
+    pub fn minimal(rng: &mut dyn RngCore, history: &mut InnovationHistory) -> Self {
+        let inputs = history.inputs();
+        let outputs = history.outputs();
+
+        let nodes = (0..inputs + outputs)
+            .map(|id| NodeGene {
+                id,
+                kind: if id < inputs {
+                    NodeKind::Input
+                } else {
+                    NodeKind::Output
+                },
+                bias: 0.0,
+                activation: if id < inputs {
+                    Activation::Identity
+                } else {
+                    Activation::Tanh
+                },
+            })
+            .collect();
+
+        let mut connections = Vec::with_capacity(inputs * outputs);
+
+        for from in 0..inputs {
+            for to in inputs..inputs + outputs {
+                connections.push(ConnectionGene {
+                    innovation: history.connection(from, to),
+                    from,
+                    to,
+                    weight: rng.gen_range(-1.0..=1.0),
+                    enabled: true,
+                });
+            }
+        }
+
+        connections.sort_by_key(|gene| gene.innovation);
+
+        Self { nodes, connections }
+    }
+
+    pub fn nodes(&self) -> &[NodeGene] {
+        &self.nodes
+    }
+
+    pub fn connections(&self) -> &[ConnectionGene] {
+        &self.connections
+    }
+
+    /// Connects two randomly chosen, not yet connected nodes; returns
+    /// `false` if there's no pair left that could be connected without
+    /// introducing a cycle.
+    pub fn add_connection(
+        &mut self,
+        rng: &mut dyn RngCore,
+        history: &mut InnovationHistory,
+    ) -> bool {
+        let mut candidates = Vec::new();
+
+        for from in self.nodes.iter().filter(|node| node.kind != NodeKind::Output) {
+            for to in self.nodes.iter().filter(|node| node.kind != NodeKind::Input) {
+                if from.id != to.id
+                    && !self.is_connected(from.id, to.id)
+                    && !self.reaches(to.id, from.id)
+                {
+                    candidates.push((from.id, to.id));
+                }
+            }
+        }
+
+        if candidates.is_empty() {
+            return false;
+        }
+
+        let (from, to) = candidates[rng.gen_range(0..candidates.len())];
+
+        self.insert_connection(ConnectionGene {
+            innovation: history.connection(from, to),
+            from,
+            to,
+            weight: rng.gen_range(-1.0..=1.0),
+            enabled: true,
+        });
+
+        true
+    }
+
+    /// Splits a randomly chosen, enabled connection `a -> b` in two: `a ->
+    /// new` (with weight of 1.0) and `new -> b` (with the original weight),
+    /// disabling the original one; returns `false` if there's no enabled
+    /// connection to split.
+    pub fn add_node(&mut self, rng: &mut dyn RngCore, history: &mut InnovationHistory) -> bool {
+        let enabled: Vec<_> = (0..self.connections.len())
+            .filter(|&idx| self.connections[idx].enabled)
+            .collect();
+
+        if enabled.is_empty() {
+            return false;
+        }
+
+        let split = &mut self.connections[enabled[rng.gen_range(0..enabled.len())]];
+        split.enabled = false;
+
+        let (innovation, from, to, weight) = (split.innovation, split.from, split.to, split.weight);
+
+        // The same connection can be split twice only if crossover enabled
+        // it back - the second split cannot reuse the node then
+        let mut node = history.split(innovation);
+
+        if self.node(node).is_some() {
+            node = history.fresh_node();
+        }
+
+        self.insert_node(NodeGene {
+            id: node,
+            kind: NodeKind::Hidden,
+            bias: 0.0,
+            activation: Activation::Tanh,
+        });
+
+        self.insert_connection(ConnectionGene {
+            innovation: history.connection(from, node),
+            from,
+            to: node,
+            weight: 1.0,
+            enabled: true,
+        });
+
+        self.insert_connection(ConnectionGene {
+            innovation: history.connection(node, to),
+            from: node,
+            to,
+            weight,
+            enabled: true,
+        });
+
+        true
+    }
+
+    /// Combines two genomes, `fitter` being the one with higher fitness.
+    ///
+    /// Matching genes (the ones with the same innovation number or id) are
+    /// inherited from a random parent, while disjoint and excess genes are
+    /// inherited from `fitter` only - so the child always has got the
+    /// fitter parent's structure. A connection disabled in either parent
+    /// stays disabled with a 75% chance.
+    pub fn crossover(rng: &mut dyn RngCore, fitter: &Self, other: &Self) -> Self {
+        let nodes = fitter
+            .nodes
+            .iter()
+            .map(|node| match other.node(node.id) {
+                Some(other) if rng.gen_bool(0.5) => other.clone(),
+                _ => node.clone(),
+            })
+            .collect();
+
+        let connections = fitter
+            .connections
+            .iter()
+            .map(|gene| match other.connection(gene.innovation) {
+                Some(other) => {
+                    let mut child = if rng.gen_bool(0.5) {
+                        gene.clone()
+                    } else {
+                        other.clone()
+                    };
+
+                    child.enabled = (gene.enabled && other.enabled) || rng.gen_bool(0.25);
+                    child
+                }
+                None => gene.clone(),
+            })
+            .collect();
+
+        Self { nodes, connections }
+    }
+
+    /// Compatibility distance:
+    ///
+    ///   excess * E / N + disjoint * D / N + weight * W
+    ///
+    /// where `E` and `D` are numbers of excess and disjoint connection genes,
+    /// `N` is the number of connection genes of the larger genome and `W`
+    /// is the average weight difference of matching genes.
+    pub fn distance(&self, other: &Self, coefficients: &CompatibilityCoefficients) -> f32 {
+        let (mut lhs, mut rhs) = (self.connections.iter().peekable(), other.connections.iter().peekable());
+        let (mut excess, mut disjoint, mut matching, mut weight_diff) = (0, 0, 0, 0.0);
+
+        loop {
+            match (lhs.peek(), rhs.peek()) {
+                (Some(a), Some(b)) if a.innovation == b.innovation => {
+                    matching += 1;
+                    weight_diff += (a.weight - b.weight).abs();
+                    lhs.next();
+                    rhs.next();
+                }
+                (Some(a), Some(b)) => {
+                    disjoint += 1;
+
+                    if a.innovation < b.innovation {
+                        lhs.next();
+                    } else {
+                        rhs.next();
+                    }
+                }
+                (Some(_), None) => {
+                    excess += 1;
+                    lhs.next();
+                }
+                (None, Some(_)) => {
+                    excess += 1;
+                    rhs.next();
+                }
+                (None, None) => break,
+            }
+        }
+
+        let genes = self.connections.len().max(other.connections.len()).max(1) as f32;
+
+        let weight_diff = if matching > 0 {
+            weight_diff / matching as f32
+        } else {
+            0.0
+        };
+
+        coefficients.excess * excess as f32 / genes
+            + coefficients.disjoint * disjoint as f32 / genes
+            + coefficients.weight * weight_diff
+    }
+
+    pub(crate) fn node(&self, id: usize) -> Option<&NodeGene> {
+        self.nodes
+            .binary_search_by_key(&id, |node| node.id)
+            .ok()
+            .map(|idx| &self.nodes[idx])
+    }
+
+    fn connection(&self, innovation: usize) -> Option<&ConnectionGene> {
+        self.connections
+            .binary_search_by_key(&innovation, |gene| gene.innovation)
+            .ok()
+            .map(|idx| &self.connections[idx])
+    }
+
+    fn insert_node(&mut self, node: NodeGene) {
+        let idx = self.nodes.partition_point(|other| other.id < node.id);
+
+        self.nodes.insert(idx, node);
+    }
+
+    fn insert_connection(&mut self, gene: ConnectionGene) {
+        let idx = self
+            .connections
+            .partition_point(|other| other.innovation < gene.innovation);
+
+        self.connections.insert(idx, gene);
+    }
+
+    fn is_connected(&self, from: usize, to: usize) -> bool {
+        self.connections
+            .iter()
+            .any(|gene| gene.from == from && gene.to == to)
+    }
+
+    // Whether there's a path `from -> ... -> to`; disabled connections
+    // count too, since crossover might enable them back
+    fn reaches(&self, from: usize, to: usize) -> bool {
+        let mut pending = vec![from];
+        let mut visited = vec![from];
+
+        while let Some(node) = pending.pop() {
+            if node == to {
+                return true;
+            }
+
+            for gene in self.connections.iter().filter(|gene| gene.from == node) {
+                if !visited.contains(&gene.to) {
+                    visited.push(gene.to);
+                    pending.push(gene.to);
+                }
+            }
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn genome(history: &mut InnovationHistory) -> NeatGenome {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        NeatGenome::minimal(&mut rng, history)
+    }
+
+    #[test]
+    fn minimal() {
+        let mut history = InnovationHistory::new(2, 1);
+        let genome = genome(&mut history);
+
+        let links: Vec<_> = genome
+            .connections()
+            .iter()
+            .map(|gene| (gene.innovation, gene.from, gene.to, gene.enabled))
+            .collect();
+
+        assert_eq!(genome.nodes().len(), 3);
+        assert_eq!(links, vec![(0, 0, 2, true), (1, 1, 2, true)]);
+    }
+
+    mod add_node {
+        use super::*;
+
+        #[test]
+        fn splits_connection() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut history = InnovationHistory::new(1, 1);
+            let mut genome = genome(&mut history);
+            let weight = genome.connections[0].weight;
+
+            assert!(genome.add_node(&mut rng, &mut history));
+
+            let links: Vec<_> = genome
+                .connections()
+                .iter()
+                .map(|gene| (gene.innovation, gene.from, gene.to, gene.enabled))
+                .collect();
+
+            assert_eq!(genome.nodes()[2].kind, NodeKind::Hidden);
+            assert_eq!(links, vec![(0, 0, 1, false), (1, 0, 2, true), (2, 2, 1, true)]);
+
+            approx::assert_relative_eq!(genome.connections[1].weight, 1.0);
+            approx::assert_relative_eq!(genome.connections[2].weight, weight);
+        }
+
+        #[test]
+        fn reuses_innovations_across_genomes() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut history = InnovationHistory::new(1, 1);
+            let mut a = genome(&mut history);
+            let mut b = genome(&mut history);
+
+            a.add_node(&mut rng, &mut history);
+            b.add_node(&mut rng, &mut history);
+
+            assert_eq!(a.nodes(), b.nodes());
+            assert_eq!(a.connections().len(), b.connections().len());
+
+            for (a, b) in a.connections().iter().zip(b.connections()) {
+                assert_eq!((a.innovation, a.from, a.to), (b.innovation, b.from, b.to));
+            }
+        }
+    }
+
+    mod add_connection {
+        use super::*;
+
+        #[test]
+        fn never_introduces_cycles() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut history = InnovationHistory::new(2, 2);
+            let mut genome = genome(&mut history);
+
+            for _ in 0..10 {
+                genome.add_node(&mut rng, &mut history);
+                genome.add_connection(&mut rng, &mut history);
+            }
+
+            // Keep adding until the graph is saturated
+            while genome.add_connection(&mut rng, &mut history) {}
+
+            for gene in genome.connections() {
+                assert!(!genome.reaches(gene.to, gene.from));
+            }
+        }
+
+        #[test]
+        fn gives_up_when_saturated() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut history = InnovationHistory::new(1, 1);
+            let mut genome = genome(&mut history);
+
+            assert!(!genome.add_connection(&mut rng, &mut history));
+        }
+    }
+
+    mod crossover {
+        use super::*;
+
+        #[test]
+        fn inherits_structure_of_fitter_parent() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut history = InnovationHistory::new(2, 1);
+            let other = genome(&mut history);
+            let mut fitter = other.clone();
+
+            fitter.add_node(&mut rng, &mut history);
+
+            let child = NeatGenome::crossover(&mut rng, &fitter, &other);
+
+            assert_eq!(child.nodes().len(), fitter.nodes().len());
+
+            for (child, fitter) in child.connections().iter().zip(fitter.connections()) {
+                assert_eq!(child.innovation, fitter.innovation);
+            }
+        }
+    }
+
+    mod distance {
+        use super::*;
+
+        #[test]
+        fn identical() {
+            let mut history = InnovationHistory::new(2, 1);
+            let genome = genome(&mut history);
+
+            approx::assert_relative_eq!(
+                genome.distance(&genome, &CompatibilityCoefficients::default()),
+                0.0
+            );
+        }
+
+        #[test]
+        fn counts_excess_disjoint_and_weights() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut history = InnovationHistory::new(2, 1);
+            let a = genome(&mut history);
+            let mut b = a.clone();
+
+            // Splitting adds two excess genes (innovations 2 and 3); the split
+            // connection is still matching, just disabled
+            b.add_node(&mut rng, &mut history);
+            b.connections[0].weight += 0.5;
+            b.connections[1].weight += 0.5;
+
+            let coefficients = CompatibilityCoefficients {
+                excess: 1.0,
+                disjoint: 2.0,
+                weight: 3.0,
+            };
+
+            // 1.0 * 2 / 4 + 2.0 * 0 / 4 + 3.0 * 0.5
+            approx::assert_relative_eq!(a.distance(&b, &coefficients), 2.0);
+            approx::assert_relative_eq!(b.distance(&a, &coefficients), 2.0);
+
+            // Dropping innovation 1 makes it disjoint, leaving 0 as the only
+            // matching gene: 1.0 * 2 / 3 + 2.0 * 1 / 3 + 3.0 * 0.5
+            b.connections.remove(1);
+
+            approx::assert_relative_eq!(a.distance(&b, &coefficients), 4.0 / 3.0 + 1.5);
+        }
+    }
+}
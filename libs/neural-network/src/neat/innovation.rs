@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+/// Hands out innovation numbers (for connection genes) and ids (for node
+/// genes), making sure that the same structural mutation happening in two
+/// different genomes ends up with the same numbers - that's what allows
+/// `NeatGenome::crossover()` to line the genomes up.
+///
+/// A single history should be shared by the whole population.
+#[derive(Clone, Debug)]
+pub struct InnovationHistory {
+    inputs: usize,
+    outputs: usize,
+    next_node: usize,
+
+    // (from, to) -> innovation number
+    connections: HashMap<(usize, usize), usize>,
+
+    // innovation number of a split connection -> id of the node it was
+    // split with
+    splits: HashMap<usize, usize>,
+}
+
+impl InnovationHistory {
+    /// Creates history for genomes with given number of inputs and outputs;
+    /// input nodes get ids `0..inputs` and output nodes follow.
+    pub fn new(inputs: usize, outputs: usize) -> Self {
+        assert!(inputs > 0);
+        assert!(outputs > 0);
+
+        Self {
+            inputs,
+            outputs,
+            next_node: inputs + outputs,
+            connections: HashMap::new(),
+            splits: HashMap::new(),
+        }
+    }
+
+    pub fn inputs(&self) -> usize {
+        self.inputs
+    }
+
+    pub fn outputs(&self) -> usize {
+        self.outputs
+    }
+
+    /// Innovation number of connection going `from -> to`.
+    pub(crate) fn connection(&mut self, from: usize, to: usize) -> usize {
+        let next = self.connections.len();
+
+        *self.connections.entry((from, to)).or_insert(next)
+    }
+
+    /// Id of the node that appears when connection with given innovation
+    /// number gets split.
+    pub(crate) fn split(&mut self, innovation: usize) -> usize {
+        if let Some(&node) = self.splits.get(&innovation) {
+            return node;
+        }
+
+        let node = self.fresh_node();
+
+        self.splits.insert(innovation, node);
+        node
+    }
+
+    /// Id that hasn't been used by any genome yet.
+    pub(crate) fn fresh_node(&mut self) -> usize {
+        self.next_node += 1;
+        self.next_node - 1
+    }
+}
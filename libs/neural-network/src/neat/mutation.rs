@@ -0,0 +1,124 @@
+use rand::{Rng, RngCore};
+
+use crate::*;
+
+/// Mutates `NeatGenome`s: perturbs weights and biases (just like
+/// `GaussianMutation` from lib-genetic-algorithm does with chromosomes) and,
+/// from time to time, grows the genome by a connection or a node.
+#[derive(Clone, Debug)]
+pub struct NeatMutation {
+    /// Probability of changing a weight or a bias
+    weight_chance: f32,
+
+    /// Magnitude of that change - touched weights will be += or -= by at
+    /// most this much
+    weight_coeff: f32,
+
+    /// Probability of adding a connection, per `mutate()` call
+    add_connection_chance: f32,
+
+    /// Probability of splitting a connection with a new node, per
+    /// `mutate()` call
+    add_node_chance: f32,
+}
+
+impl NeatMutation {
+    pub fn new(weight_chance: f32, weight_coeff: f32) -> Self {
+        assert!((0.0..=1.0).contains(&weight_chance));
+
+        Self {
+            weight_chance,
+            weight_coeff,
+            add_connection_chance: 0.05,
+            add_node_chance: 0.03,
+        }
+    }
+
+    pub fn with_add_connection_chance(mut self, chance: f32) -> Self {
+        assert!((0.0..=1.0).contains(&chance));
+
+        self.add_connection_chance = chance;
+        self
+    }
+
+    pub fn with_add_node_chance(mut self, chance: f32) -> Self {
+        assert!((0.0..=1.0).contains(&chance));
+
+        self.add_node_chance = chance;
+        self
+    }
+
+    pub fn mutate(
+        &self,
+        rng: &mut dyn RngCore,
+        genome: &mut NeatGenome,
+        history: &mut InnovationHistory,
+    ) {
+        let weights = genome.connections.iter_mut().map(|gene| &mut gene.weight);
+
+        let biases = genome
+            .nodes
+            .iter_mut()
+            .filter(|node| node.kind != NodeKind::Input)
+            .map(|node| &mut node.bias);
+
+        for gene in weights.chain(biases) {
+            let sign = if rng.gen_bool(0.5) { -1.0 } else { 1.0 };
+
+            if rng.gen_bool(self.weight_chance as _) {
+                *gene += sign * self.weight_coeff * rng.gen::<f32>();
+            }
+        }
+
+        if rng.gen_bool(self.add_connection_chance as _) {
+            genome.add_connection(rng, history);
+        }
+
+        if rng.gen_bool(self.add_node_chance as _) {
+            genome.add_node(rng, history);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn grows_genome() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut history = InnovationHistory::new(3, 2);
+        let mut genome = NeatGenome::minimal(&mut rng, &mut history);
+
+        let mutation = NeatMutation::new(0.0, 0.0)
+            .with_add_connection_chance(1.0)
+            .with_add_node_chance(1.0);
+
+        for _ in 0..5 {
+            mutation.mutate(&mut rng, &mut genome, &mut history);
+        }
+
+        // Each round adds a node along with two connections, plus another
+        // connection - except for the first round, when inputs and outputs
+        // are already fully connected
+        assert_eq!(genome.nodes().len(), 5 + 5);
+        assert_eq!(genome.connections().len(), 6 + 5 * 2 + 4);
+    }
+
+    #[test]
+    fn given_zero_chances_does_not_change_genome() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut history = InnovationHistory::new(3, 2);
+        let mut genome = NeatGenome::minimal(&mut rng, &mut history);
+        let original = genome.clone();
+
+        NeatMutation::new(0.0, 1.0)
+            .with_add_connection_chance(0.0)
+            .with_add_node_chance(0.0)
+            .mutate(&mut rng, &mut genome, &mut history);
+
+        assert_eq!(genome, original);
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use crate::*;
+
+/// Phenotype of a `NeatGenome` - a feed-forward network of arbitrary shape,
+/// with neurons evaluated in topological order.
+#[derive(Clone, Debug)]
+pub struct NeatNetwork {
+    inputs: usize,
+
+    // Neuron `n` stores its output at slot `inputs + n`, right after the
+    // inputs (which occupy slots `0..inputs`)
+    neurons: Vec<NeatNeuron>,
+    outputs: Vec<usize>,
+}
+
+#[derive(Clone, Debug)]
+struct NeatNeuron {
+    bias: f32,
+    activation: Activation,
+
+    // (slot, weight)
+    incoming: Vec<(usize, f32)>,
+}
+
+impl NeatNetwork {
+    pub fn from_genome(genome: &NeatGenome) -> Self {
+        let mut slots = HashMap::new();
+
+        for node in genome.nodes.iter().filter(|node| node.kind == NodeKind::Input) {
+            slots.insert(node.id, slots.len());
+        }
+
+        let inputs = slots.len();
+
+        // Kahn's algorithm; disabled connections take part in ordering too,
+        // which is fine since genomes never contain cycles
+        let mut pending_inputs: HashMap<usize, usize> = HashMap::new();
+
+        for gene in &genome.connections {
+            if !slots.contains_key(&gene.from) {
+                *pending_inputs.entry(gene.to).or_default() += 1;
+            }
+        }
+
+        let mut ready: Vec<_> = genome
+            .nodes
+            .iter()
+            .rev()
+            .filter(|node| node.kind != NodeKind::Input && !pending_inputs.contains_key(&node.id))
+            .collect();
+
+        let mut neurons = Vec::with_capacity(genome.nodes.len() - inputs);
+
+        while let Some(node) = ready.pop() {
+            let incoming = genome
+                .connections
+                .iter()
+                .filter(|gene| gene.enabled && gene.to == node.id)
+                .map(|gene| (slots[&gene.from], gene.weight))
+                .collect();
+
+            slots.insert(node.id, slots.len());
+
+            neurons.push(NeatNeuron {
+                bias: node.bias,
+                activation: node.activation.clone(),
+                incoming,
+            });
+
+            for gene in genome.connections.iter().filter(|gene| gene.from == node.id) {
+                let pending = pending_inputs.get_mut(&gene.to).unwrap();
+                *pending -= 1;
+
+                if *pending == 0 {
+                    ready.push(genome.node(gene.to).unwrap());
+                }
+            }
+        }
+
+        assert_eq!(inputs + neurons.len(), genome.nodes.len(), "genome contains a cycle");
+
+        let outputs = genome
+            .nodes
+            .iter()
+            .filter(|node| node.kind == NodeKind::Output)
+            .map(|node| slots[&node.id])
+            .collect();
+
+        Self {
+            inputs,
+            neurons,
+            outputs,
+        }
+    }
+
+    pub fn propagate(&self, inputs: Vec<f32>) -> Vec<f32> {
+        self.propagate_into(&inputs, &mut Scratch::new()).to_vec()
+    }
+
+    /// Allocation-free version of `propagate()`.
+    pub fn propagate_into<'a>(&self, inputs: &[f32], scratch: &'a mut Scratch) -> &'a [f32] {
+        assert_eq!(inputs.len(), self.inputs);
+
+        let Scratch { front, back, .. } = scratch;
+
+        back.clear();
+        back.extend_from_slice(inputs);
+
+        for neuron in &self.neurons {
+            let sum = neuron
+                .incoming
+                .iter()
+                .map(|&(slot, weight)| back[slot] * weight)
+                .sum::<f32>();
+
+            back.push(neuron.activation.apply(neuron.bias + sum));
+        }
+
+        front.clear();
+        front.extend(self.outputs.iter().map(|&slot| back[slot]));
+        front
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn propagate() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut history = InnovationHistory::new(1, 1);
+        let mut genome = NeatGenome::minimal(&mut rng, &mut history);
+
+        genome.add_node(&mut rng, &mut history);
+
+        // in --0.5--> out
+        // in --0.5--> hidden --2.0--> out
+        //
+        // with the hidden neuron having a bias of 0.1 and using ReLU
+        genome.connections[0].enabled = true;
+        genome.connections[0].weight = 0.5;
+        genome.connections[1].weight = 0.5;
+        genome.connections[2].weight = 2.0;
+        genome.nodes[2].bias = 0.1;
+        genome.nodes[2].activation = Activation::ReLU;
+
+        let network = NeatNetwork::from_genome(&genome);
+        let actual = network.propagate(vec![0.4]);
+
+        // tanh(0.5 * 0.4 + 2.0 * relu(0.1 + 0.5 * 0.4))
+        let expected = [(0.2_f32 + 2.0 * 0.3).tanh()];
+
+        approx::assert_relative_eq!(actual.as_slice(), expected.as_ref());
+    }
+
+    #[test]
+    fn ignores_disabled_connections() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut history = InnovationHistory::new(1, 1);
+        let mut genome = NeatGenome::minimal(&mut rng, &mut history);
+
+        genome.connections[0].enabled = false;
+
+        let actual = NeatNetwork::from_genome(&genome).propagate(vec![1.0]);
+
+        approx::assert_relative_eq!(actual.as_slice(), [0.0].as_ref());
+    }
+}
@@ -0,0 +1,158 @@
+use crate::*;
+
+/// Group of genomes similar enough to compete (mostly) with each other.
+#[derive(Clone, Debug)]
+pub struct Species {
+    id: usize,
+    representative: NeatGenome,
+
+    // Indices into the slice passed to `Speciation::speciate()`
+    members: Vec<usize>,
+}
+
+impl Species {
+    /// Identifies the species across generations.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Genome new candidates are compared against.
+    pub fn representative(&self) -> &NeatGenome {
+        &self.representative
+    }
+
+    pub fn members(&self) -> &[usize] {
+        &self.members
+    }
+}
+
+/// Splits population into species by compatibility distance; keep one
+/// instance around for the whole run, so that species persist from one
+/// generation to another.
+#[derive(Clone, Debug)]
+pub struct Speciation {
+    /// Genomes closer than this to a species' representative belong to
+    /// that species
+    threshold: f32,
+    coefficients: CompatibilityCoefficients,
+    species: Vec<Species>,
+    next_id: usize,
+}
+
+impl Speciation {
+    pub fn new(threshold: f32) -> Self {
+        assert!(threshold > 0.0);
+
+        Self {
+            threshold,
+            coefficients: Default::default(),
+            species: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn with_coefficients(mut self, coefficients: CompatibilityCoefficients) -> Self {
+        self.coefficients = coefficients;
+        self
+    }
+
+    pub fn species(&self) -> &[Species] {
+        &self.species
+    }
+
+    /// Assigns each genome to the first species whose representative is
+    /// close enough, founding new species for the ones that don't fit
+    /// anywhere; species left without members die out.
+    ///
+    /// Afterwards the first member of each species becomes its
+    /// representative for the next generation.
+    pub fn speciate(&mut self, genomes: &[NeatGenome]) -> &[Species] {
+        for species in &mut self.species {
+            species.members.clear();
+        }
+
+        for (idx, genome) in genomes.iter().enumerate() {
+            let species = self.species.iter_mut().find(|species| {
+                species.representative.distance(genome, &self.coefficients) < self.threshold
+            });
+
+            match species {
+                Some(species) => species.members.push(idx),
+                None => {
+                    self.species.push(Species {
+                        id: self.next_id,
+                        representative: genome.clone(),
+                        members: vec![idx],
+                    });
+
+                    self.next_id += 1;
+                }
+            }
+        }
+
+        self.species.retain(|species| !species.members.is_empty());
+
+        for species in &mut self.species {
+            species.representative = genomes[species.members[0]].clone();
+        }
+
+        &self.species
+    }
+
+    /// Explicit fitness sharing - divides each genome's fitness by the size
+    /// of its species, so that a single large species cannot take over the
+    /// whole population; expects fitness in the same order as genomes given
+    /// to the latest `speciate()`.
+    pub fn shared_fitness(&self, fitness: &[f32]) -> Vec<f32> {
+        let mut shared = fitness.to_vec();
+
+        for species in &self.species {
+            for &member in &species.members {
+                shared[member] /= species.members.len() as f32;
+            }
+        }
+
+        shared
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn speciate() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut history = InnovationHistory::new(2, 1);
+        let a = NeatGenome::minimal(&mut rng, &mut history);
+        let mut b = a.clone();
+
+        b.add_node(&mut rng, &mut history);
+        b.add_node(&mut rng, &mut history);
+
+        let genomes = vec![a.clone(), b.clone(), a.clone(), b];
+        let mut speciation = Speciation::new(0.5);
+
+        let members: Vec<_> = speciation
+            .speciate(&genomes)
+            .iter()
+            .map(|species| (species.id(), species.members().to_vec()))
+            .collect();
+
+        assert_eq!(members, vec![(0, vec![0, 2]), (1, vec![1, 3])]);
+
+        let shared = speciation.shared_fitness(&[1.0, 2.0, 3.0, 4.0]);
+        approx::assert_relative_eq!(shared.as_slice(), [0.5, 1.0, 1.5, 2.0].as_ref());
+
+        // The second species dies out, and its id is not reused
+        let members: Vec<_> = speciation
+            .speciate(&[a])
+            .iter()
+            .map(|species| (species.id(), species.members().to_vec()))
+            .collect();
+
+        assert_eq!(members, vec![(0, vec![0])]);
+    }
+}
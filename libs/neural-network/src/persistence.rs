@@ -0,0 +1,742 @@
+use std::convert::TryFrom;
+use std::path::Path;
+use std::{fmt, fs, io};
+
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Version of the on-disk format; bump it whenever the layout of
+/// `SerializedNetwork` or of the binary encoding changes.
+///
+/// Files of other versions are rejected with
+/// `PersistenceError::UnsupportedVersion`.
+pub const FORMAT_VERSION: u16 = 1;
+
+/// First bytes of every binary-encoded network, used by `Network::load()` to
+/// tell binary files apart from JSON ones.
+const MAGIC: &[u8; 4] = b"SLNN";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Human-readable, handy for inspecting champion brains by hand
+    Json,
+
+    /// Compact little-endian encoding, prefixed with `MAGIC` and the version
+    Binary,
+}
+
+// What actually lands on disk: the topology plus weights in the very same
+// order `Network::weights()` yields them, so that the format doesn't depend
+// on how `Layer` and `Neuron` happen to store things internally.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct SerializedNetwork {
+    version: u16,
+    topology: Vec<LayerTopology>,
+    weights: Vec<f32>,
+
+    // Present only if some connections are disabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mask: Option<Vec<bool>>,
+}
+
+impl From<&Network> for SerializedNetwork {
+    fn from(network: &Network) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            topology: network.topology(),
+            weights: network.weights(),
+            mask: stored_mask(network),
+        }
+    }
+}
+
+// Mask worth storing, i.e. one with some connections disabled
+fn stored_mask(network: &Network) -> Option<Vec<bool>> {
+    let mask = network.mask();
+
+    if mask.contains(&false) {
+        Some(mask)
+    } else {
+        None
+    }
+}
+
+impl TryFrom<SerializedNetwork> for Network {
+    type Error = PersistenceError;
+
+    fn try_from(network: SerializedNetwork) -> Result<Self, Self::Error> {
+        check_version(network.version)?;
+
+        Ok(Network::try_from_masked_weights(
+            &network.topology,
+            network.weights,
+            network.mask.as_deref(),
+        )?)
+    }
+}
+
+// Only `f32` networks can be saved, since that's what the format stores
+// weights as - other scalars can be `cast()` there and back
+impl Serialize for Network {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedNetwork::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Network {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let network = SerializedNetwork::deserialize(deserializer)?;
+
+        Network::try_from(network).map_err(serde::de::Error::custom)
+    }
+}
+
+// Just the version of a JSON-encoded network, so that it can be checked
+// before the rest (whose layout depends on it) gets deserialized
+#[derive(Deserialize)]
+struct JsonHeader {
+    version: u16,
+}
+
+fn check_version(version: u16) -> Result<(), PersistenceError> {
+    if version != FORMAT_VERSION {
+        return Err(PersistenceError::UnsupportedVersion {
+            found: version,
+            supported: FORMAT_VERSION,
+        });
+    }
+
+    Ok(())
+}
+
+impl Network {
+    pub fn to_json(&self) -> Result<String, PersistenceError> {
+        // Same check as the binary encoding does, since otherwise serde
+        // would just report a variant it cannot serialize
+        for layer in &self.topology() {
+            encode_activation(&layer.activation)?;
+        }
+
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, PersistenceError> {
+        let JsonHeader { version } = serde_json::from_str(json)?;
+
+        check_version(version)?;
+
+        Network::try_from(serde_json::from_str::<SerializedNetwork>(json)?)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, PersistenceError> {
+        let topology = self.topology();
+        let weights = self.weights();
+        let mut bytes = Vec::with_capacity(16 + 11 * topology.len() + 4 * weights.len());
+
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(topology.len() as u32).to_le_bytes());
+
+        for layer in &topology {
+            let (tag, param) = encode_activation(&layer.activation)?;
+
+            bytes.extend_from_slice(&(layer.neurons as u32).to_le_bytes());
+            bytes.push(tag);
+            bytes.extend_from_slice(&param.to_le_bytes());
+            encode_kind(layer.kind, &mut bytes);
+
+            // Sources are stored as a flag followed by (if set) their count
+            // and indices
+            match &layer.sources {
+                Some(sources) => {
+                    bytes.push(1);
+                    bytes.extend_from_slice(&(sources.len() as u32).to_le_bytes());
+
+                    for &source in sources {
+                        bytes.extend_from_slice(&(source as u32).to_le_bytes());
+                    }
+                }
+                None => bytes.push(0),
+            }
+        }
+
+        bytes.extend_from_slice(&(weights.len() as u32).to_le_bytes());
+
+        for weight in weights {
+            bytes.extend_from_slice(&weight.to_le_bytes());
+        }
+
+        // Mask is stored as a flag followed by (if set) one bit per weight,
+        // least significant bit first
+        match stored_mask(self) {
+            Some(mask) => {
+                bytes.push(1);
+
+                for bits in mask.chunks(8) {
+                    let byte = bits
+                        .iter()
+                        .enumerate()
+                        .fold(0, |byte, (idx, &bit)| byte | ((bit as u8) << idx));
+
+                    bytes.push(byte);
+                }
+            }
+            None => bytes.push(0),
+        }
+
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PersistenceError> {
+        let mut reader = Reader { bytes };
+
+        if !bytes.starts_with(MAGIC) {
+            return Err(PersistenceError::InvalidHeader);
+        }
+
+        reader.take(MAGIC.len())?;
+
+        let version = u16::from_le_bytes(reader.array()?);
+
+        check_version(version)?;
+
+        let layers = u32::from_le_bytes(reader.array()?) as usize;
+
+        let topology = (0..layers)
+            .map(|_| {
+                let neurons = u32::from_le_bytes(reader.array()?) as usize;
+                let tag = reader.take(1)?[0];
+                let param = f32::from_le_bytes(reader.array()?);
+
+                let kind = decode_kind(&mut reader)?;
+
+                let sources = if reader.take(1)?[0] != 0 {
+                    let len = u32::from_le_bytes(reader.array()?) as usize;
+
+                    Some(
+                        (0..len)
+                            .map(|_| Ok(u32::from_le_bytes(reader.array()?) as usize))
+                            .collect::<Result<_, PersistenceError>>()?,
+                    )
+                } else {
+                    None
+                };
+
+                Ok(LayerTopology {
+                    neurons,
+                    activation: decode_activation(tag, param)?,
+                    kind,
+                    sources,
+                })
+            })
+            .collect::<Result<_, PersistenceError>>()?;
+
+        let weights_len = u32::from_le_bytes(reader.array()?) as usize;
+
+        let weights = (0..weights_len)
+            .map(|_| Ok(f32::from_le_bytes(reader.array()?)))
+            .collect::<Result<_, PersistenceError>>()?;
+
+        let mask = if reader.take(1)?[0] != 0 {
+            let bytes = reader.take(weights_len.div_ceil(8))?;
+
+            Some(
+                (0..weights_len)
+                    .map(|idx| bytes[idx / 8] & (1 << (idx % 8)) != 0)
+                    .collect(),
+            )
+        } else {
+            None
+        };
+
+        if !reader.bytes.is_empty() {
+            return Err(PersistenceError::TrailingBytes(reader.bytes.len()));
+        }
+
+        Network::try_from(SerializedNetwork {
+            version,
+            topology,
+            weights,
+            mask,
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>, format: Format) -> Result<(), PersistenceError> {
+        let bytes = match format {
+            Format::Json => self.to_json()?.into_bytes(),
+            Format::Binary => self.to_bytes()?,
+        };
+
+        Ok(fs::write(path, bytes)?)
+    }
+
+    /// Loads a network saved with `Network::save()`, detecting the format
+    /// on its own.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PersistenceError> {
+        let bytes = fs::read(path)?;
+
+        if bytes.starts_with(MAGIC) {
+            Self::from_bytes(&bytes)
+        } else {
+            let json = std::str::from_utf8(&bytes).map_err(|_| PersistenceError::InvalidHeader)?;
+
+            Self::from_json(json)
+        }
+    }
+
+    /// Like `Network::load()`, but additionally makes sure the network has
+    /// got the expected topology (e.g. the one `Brain` is built with).
+    pub fn load_with_topology(
+        path: impl AsRef<Path>,
+        expected: &[LayerTopology],
+    ) -> Result<Self, PersistenceError> {
+        let network = Self::load(path)?;
+        let found = network.topology();
+
+        // Input layer's activation is never applied, so it doesn't matter
+        let matches = expected.len() == found.len()
+            && expected[0].neurons == found[0].neurons
+            && expected[1..] == found[1..];
+
+        if !matches {
+            return Err(PersistenceError::TopologyMismatch {
+                expected: expected.to_vec(),
+                found,
+            });
+        }
+
+        Ok(network)
+    }
+}
+
+#[derive(Debug)]
+pub enum PersistenceError {
+    Io(io::Error),
+    Json(serde_json::Error),
+
+    /// Data doesn't start with the expected magic bytes
+    InvalidHeader,
+
+    /// Data ends before the network does
+    Truncated,
+
+    /// Data ends with bytes that don't belong to the network
+    TrailingBytes(usize),
+
+    UnsupportedVersion {
+        found: u16,
+        supported: u16,
+    },
+
+    UnknownActivation(u8),
+    UnknownLayerKind(u8),
+
+    /// `Activation::Custom` holds a closure, which cannot be saved
+    CustomActivation(String),
+
+    /// Stored weights don't fit the stored topology
+    Network(NetworkError),
+
+    TopologyMismatch {
+        expected: Vec<LayerTopology>,
+        found: Vec<LayerTopology>,
+    },
+}
+
+impl fmt::Display for PersistenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "i/o error: {}", err),
+            Self::Json(err) => write!(f, "malformed json: {}", err),
+            Self::InvalidHeader => write!(f, "not a serialized network (invalid header)"),
+            Self::Truncated => write!(f, "unexpected end of data"),
+            Self::TrailingBytes(len) => write!(f, "got {} unexpected trailing bytes", len),
+            Self::UnsupportedVersion { found, supported } => write!(
+                f,
+                "unsupported format version {} (supported: {})",
+                found, supported
+            ),
+            Self::UnknownActivation(tag) => write!(f, "unknown activation tag {}", tag),
+            Self::UnknownLayerKind(tag) => write!(f, "unknown layer kind tag {}", tag),
+            Self::CustomActivation(name) => {
+                write!(f, "custom activation `{}` cannot be serialized", name)
+            }
+            Self::Network(err) => write!(f, "invalid network: {}", err),
+            Self::TopologyMismatch { expected, found } => write!(
+                f,
+                "expected layers {:?}, got {:?}",
+                describe(expected),
+                describe(found)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PersistenceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Json(err) => Some(err),
+            Self::Network(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PersistenceError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<NetworkError> for PersistenceError {
+    fn from(err: NetworkError) -> Self {
+        Self::Network(err)
+    }
+}
+
+impl From<serde_json::Error> for PersistenceError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+fn describe(topology: &[LayerTopology]) -> Vec<String> {
+    topology
+        .iter()
+        .map(|layer| format!("{} x {:?}", layer.neurons, layer.activation))
+        .collect()
+}
+
+fn encode_activation(activation: &Activation) -> Result<(u8, f32), PersistenceError> {
+    Ok(match activation {
+        Activation::ReLU => (0, 0.0),
+        Activation::LeakyReLU(slope) => (1, *slope),
+        Activation::Sigmoid => (2, 0.0),
+        Activation::Tanh => (3, 0.0),
+        Activation::Identity => (4, 0.0),
+        Activation::Softsign => (5, 0.0),
+        Activation::Custom(custom) => {
+            return Err(PersistenceError::CustomActivation(custom.name().into()));
+        }
+    })
+}
+
+fn decode_activation(tag: u8, param: f32) -> Result<Activation, PersistenceError> {
+    Ok(match tag {
+        0 => Activation::ReLU,
+        1 => Activation::LeakyReLU(param),
+        2 => Activation::Sigmoid,
+        3 => Activation::Tanh,
+        4 => Activation::Identity,
+        5 => Activation::Softsign,
+        _ => return Err(PersistenceError::UnknownActivation(tag)),
+    })
+}
+
+// Kinds are stored as a tag, followed by their parameters (if any)
+fn encode_kind(kind: LayerKind, bytes: &mut Vec<u8>) {
+    match kind {
+        LayerKind::Dense => bytes.push(0),
+        LayerKind::Elman => bytes.push(1),
+        LayerKind::Gru => bytes.push(2),
+        LayerKind::CircularConv { kernel } => {
+            bytes.push(3);
+            bytes.extend_from_slice(&(kernel as u32).to_le_bytes());
+        }
+    }
+}
+
+fn decode_kind(reader: &mut Reader) -> Result<LayerKind, PersistenceError> {
+    let tag = reader.take(1)?[0];
+
+    Ok(match tag {
+        0 => LayerKind::Dense,
+        1 => LayerKind::Elman,
+        2 => LayerKind::Gru,
+        3 => LayerKind::CircularConv {
+            kernel: u32::from_le_bytes(reader.array()?) as usize,
+        },
+        _ => return Err(PersistenceError::UnknownLayerKind(tag)),
+    })
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], PersistenceError> {
+        if self.bytes.len() < len {
+            return Err(PersistenceError::Truncated);
+        }
+
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], PersistenceError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+
+        Ok(array)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn topology() -> Vec<LayerTopology> {
+        vec![
+            LayerTopology::new(3),
+            LayerTopology::new(2)
+                .with_activation(Activation::LeakyReLU(0.1))
+                .with_kind(LayerKind::Elman),
+            LayerTopology::new(1).with_activation(Activation::Tanh),
+        ]
+    }
+
+    fn network() -> Network {
+        let weights = (0..LayerTopology::weights_len(&topology())).map(|n| n as f32 / 10.0);
+
+        Network::from_weights(&topology(), weights)
+    }
+
+    // Hidden layer sees the first input group, while the output layer sees
+    // both of them plus the hidden layer
+    fn skip_topology() -> Vec<LayerTopology> {
+        vec![
+            LayerTopology::new(2),
+            LayerTopology::input(1),
+            LayerTopology::new(2),
+            LayerTopology::new(1)
+                .with_activation(Activation::Tanh)
+                .with_sources([0, 1, 2]),
+        ]
+    }
+
+    fn skip_network() -> Network {
+        let weights = (0..LayerTopology::weights_len(&skip_topology())).map(|n| n as f32 / 10.0);
+
+        Network::from_weights(&skip_topology(), weights)
+    }
+
+    fn conv_topology() -> Vec<LayerTopology> {
+        vec![
+            LayerTopology::new(4),
+            LayerTopology::new(8).with_kind(LayerKind::CircularConv { kernel: 3 }),
+            LayerTopology::new(1).with_activation(Activation::Tanh),
+        ]
+    }
+
+    fn conv_network() -> Network {
+        let weights = (0..LayerTopology::weights_len(&conv_topology())).map(|n| n as f32 / 10.0);
+
+        Network::from_weights(&conv_topology(), weights)
+    }
+
+    fn pruned_network() -> Network {
+        let mut network = network();
+        network.prune(0.45);
+        network
+    }
+
+    fn assert_same_weights(actual: &Network, expected: &Network) {
+        let actual = actual.weights();
+        let expected = expected.weights();
+
+        approx::assert_relative_eq!(actual.as_slice(), expected.as_slice());
+    }
+
+    mod json {
+        use super::*;
+
+        #[test]
+        fn roundtrip() {
+            let json = network().to_json().unwrap();
+            let actual = Network::from_json(&json).unwrap();
+
+            assert_eq!(actual.topology(), topology());
+            assert_same_weights(&actual, &network());
+        }
+
+        #[test]
+        fn roundtrip_with_sources() {
+            let data = skip_network().to_json().unwrap();
+            let actual = Network::from_json(&data).unwrap();
+
+            assert_eq!(actual.topology(), skip_topology());
+            assert_same_weights(&actual, &skip_network());
+        }
+
+        #[test]
+        fn roundtrip_with_convolution() {
+            let data = conv_network().to_json().unwrap();
+            let actual = Network::from_json(&data).unwrap();
+
+            assert_eq!(actual.topology(), conv_topology());
+            assert_same_weights(&actual, &conv_network());
+        }
+
+        #[test]
+        fn roundtrip_with_mask() {
+            let data = pruned_network().to_json().unwrap();
+            let actual = Network::from_json(&data).unwrap();
+
+            assert_eq!(actual.mask(), pruned_network().mask());
+            assert_same_weights(&actual, &pruned_network());
+        }
+
+        #[test]
+        fn rejects_other_versions() {
+            let json = network().to_json().unwrap().replace("\"version\": 1", "\"version\": 2");
+
+            assert!(matches!(
+                Network::from_json(&json),
+                Err(PersistenceError::UnsupportedVersion { found: 2, supported: 1 })
+            ));
+        }
+
+        #[test]
+        fn checks_version_before_layout() {
+            // E.g. a newer file, with layers laid out in some other way
+            let json = r#"{ "version": 2, "layers": [] }"#;
+
+            assert!(matches!(
+                Network::from_json(json),
+                Err(PersistenceError::UnsupportedVersion { found: 2, supported: 1 })
+            ));
+        }
+
+        #[test]
+        fn rejects_custom_activations() {
+            let network = Network::from_weights(
+                &[
+                    LayerTopology::new(1),
+                    LayerTopology::new(1).with_activation(Activation::custom("double", |x| 2.0 * x)),
+                ],
+                vec![0.1, 0.2],
+            );
+
+            assert!(matches!(
+                network.to_json(),
+                Err(PersistenceError::CustomActivation(name)) if name == "double"
+            ));
+
+            let path = std::env::temp_dir().join(format!("lib-neural-network-{}-custom.json", std::process::id()));
+
+            assert!(matches!(
+                network.save(&path, Format::Json),
+                Err(PersistenceError::CustomActivation(_))
+            ));
+
+            assert!(!path.exists());
+        }
+    }
+
+    mod binary {
+        use super::*;
+
+        #[test]
+        fn roundtrip() {
+            let bytes = network().to_bytes().unwrap();
+            let actual = Network::from_bytes(&bytes).unwrap();
+
+            assert_eq!(actual.topology(), topology());
+            assert_same_weights(&actual, &network());
+        }
+
+        #[test]
+        fn roundtrip_with_sources() {
+            let data = skip_network().to_bytes().unwrap();
+            let actual = Network::from_bytes(&data).unwrap();
+
+            assert_eq!(actual.topology(), skip_topology());
+            assert_same_weights(&actual, &skip_network());
+        }
+
+        #[test]
+        fn roundtrip_with_convolution() {
+            let data = conv_network().to_bytes().unwrap();
+            let actual = Network::from_bytes(&data).unwrap();
+
+            assert_eq!(actual.topology(), conv_topology());
+            assert_same_weights(&actual, &conv_network());
+        }
+
+        #[test]
+        fn roundtrip_with_mask() {
+            let data = pruned_network().to_bytes().unwrap();
+            let actual = Network::from_bytes(&data).unwrap();
+
+            assert_eq!(actual.mask(), pruned_network().mask());
+            assert_same_weights(&actual, &pruned_network());
+        }
+
+        #[test]
+        fn rejects_other_versions() {
+            let mut bytes = network().to_bytes().unwrap();
+            bytes[4] = 2;
+
+            assert!(matches!(
+                Network::from_bytes(&bytes),
+                Err(PersistenceError::UnsupportedVersion { found: 2, supported: 1 })
+            ));
+        }
+
+        #[test]
+        fn rejects_truncated_data() {
+            let bytes = network().to_bytes().unwrap();
+
+            assert!(matches!(
+                Network::from_bytes(&bytes[..bytes.len() - 1]),
+                Err(PersistenceError::Truncated)
+            ));
+
+            // Just the magic bytes and the version
+            assert!(matches!(Network::from_bytes(&bytes[..6]), Err(PersistenceError::Truncated)));
+        }
+
+        #[test]
+        fn rejects_other_data() {
+            assert!(matches!(Network::from_bytes(b"SL"), Err(PersistenceError::InvalidHeader)));
+            assert!(matches!(Network::from_bytes(b"{}"), Err(PersistenceError::InvalidHeader)));
+        }
+    }
+
+    mod save_and_load {
+        use super::*;
+
+        fn path(name: &str) -> std::path::PathBuf {
+            std::env::temp_dir().join(format!("lib-neural-network-{}-{}", std::process::id(), name))
+        }
+
+        #[test]
+        fn detects_format() {
+            for (name, format) in [("net.json", Format::Json), ("net.bin", Format::Binary)] {
+                let path = path(name);
+
+                network().save(&path, format).unwrap();
+                let actual = Network::load(&path).unwrap();
+                fs::remove_file(&path).unwrap();
+
+                assert_same_weights(&actual, &network());
+            }
+        }
+
+        #[test]
+        fn rejects_other_topologies() {
+            let path = path("mismatch.bin");
+
+            network().save(&path, Format::Binary).unwrap();
+            let err = Network::load_with_topology(&path, &[LayerTopology::new(3), LayerTopology::new(1)]);
+            fs::remove_file(&path).unwrap();
+
+            assert!(matches!(err, Err(PersistenceError::TopologyMismatch { .. })));
+        }
+    }
+}
@@ -0,0 +1,423 @@
+use rand::Rng;
+
+use crate::*;
+
+/// Shape of a `PlasticNetwork`: its layers, plus how fast and how far its
+/// weights may change.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlasticTopology {
+    pub layers: Vec<LayerTopology>,
+
+    /// How much a single `propagate()` changes the weights (the rule's eta)
+    pub learning_rate: f32,
+
+    /// Weights are kept within <-weight_limit, weight_limit>, so that
+    /// self-reinforcing connections don't grow without bounds
+    pub weight_limit: f32,
+}
+
+impl PlasticTopology {
+    pub fn new(layers: impl Into<Vec<LayerTopology>>) -> Self {
+        Self {
+            layers: layers.into(),
+            learning_rate: 0.01,
+            weight_limit: 4.0,
+        }
+    }
+
+    pub fn with_learning_rate(mut self, learning_rate: f32) -> Self {
+        assert!(learning_rate >= 0.0);
+
+        self.learning_rate = learning_rate;
+        self
+    }
+
+    pub fn with_weight_limit(mut self, weight_limit: f32) -> Self {
+        assert!(weight_limit > 0.0);
+
+        self.weight_limit = weight_limit;
+        self
+    }
+
+    /// Number of connections (i.e. weights, not counting biases) - each of
+    /// them has got its own set of coefficients.
+    pub fn connections(&self) -> usize {
+        let biases: usize = (0..self.layers.len())
+            .filter(|&idx| !LayerTopology::is_input(&self.layers, idx))
+            .map(|idx| self.layers[idx].neurons)
+            .sum();
+
+        LayerTopology::weights_len(&self.layers) - biases
+    }
+
+    /// Number of values `PlasticNetwork::weights()` returns - that is: the
+    /// same as for a `Network`, plus four coefficients per connection.
+    pub fn weights_len(&self) -> usize {
+        LayerTopology::weights_len(&self.layers) + 4 * self.connections()
+    }
+
+    fn validate(&self) -> Result<(), NetworkError> {
+        LayerTopology::wiring(&self.layers)?;
+
+        for (layer, topology) in self.layers.iter().enumerate() {
+            if !LayerTopology::is_input(&self.layers, layer) && topology.kind != LayerKind::Dense {
+                return Err(NetworkError::UnsupportedLayerKind {
+                    layer,
+                    kind: topology.kind,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Feed-forward network whose weights keep changing as it's being used,
+/// following the ABCD Hebbian rule:
+///
+///   dw_ij = eta * (A_ij * x_j * y_i + B_ij * x_j + C_ij * y_i + D_ij)
+///
+/// where `x_j` is the connection's input and `y_i` is its neuron's output.
+///
+/// The coefficients (along with the initial weights) are meant to be
+/// evolved, while the weights themselves are learned during the network's
+/// lifetime - `weights()` always returns the initial ones, so whatever got
+/// learned is not inherited. Only dense layers are supported.
+#[derive(Clone, Debug)]
+pub struct PlasticNetwork {
+    topology: PlasticTopology,
+
+    // Weights the network started with, brought back by `reset_state()`
+    initial: Network,
+    network: Network,
+
+    // One `[A, B, C, D]` per connection, in `Network::weights()` order
+    rules: Vec<[f32; 4]>,
+}
+
+impl PlasticNetwork {
+    pub fn random(rng: &mut dyn rand::RngCore, topology: &PlasticTopology) -> Self {
+        Self::try_random(rng, topology).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_random(
+        rng: &mut dyn rand::RngCore,
+        topology: &PlasticTopology,
+    ) -> Result<Self, NetworkError> {
+        topology.validate()?;
+
+        let weights = (0..topology.weights_len())
+            .map(|_| rng.gen_range(-1.0..=1.0))
+            .collect::<Vec<_>>();
+
+        Self::try_from_weights(topology, weights)
+    }
+
+    pub fn from_weights(
+        topology: &PlasticTopology,
+        weights: impl IntoIterator<Item = f32>,
+    ) -> Self {
+        Self::try_from_weights(topology, weights).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_from_weights(
+        topology: &PlasticTopology,
+        weights: impl IntoIterator<Item = f32>,
+    ) -> Result<Self, NetworkError> {
+        topology.validate()?;
+
+        let mut genes: Vec<f32> = weights.into_iter().collect();
+        let expected = topology.weights_len();
+
+        if genes.len() < expected {
+            return Err(NetworkError::NotEnoughWeights {
+                expected,
+                found: genes.len(),
+            });
+        }
+
+        if genes.len() > expected {
+            return Err(NetworkError::TooManyWeights {
+                expected,
+                found: genes.len(),
+            });
+        }
+
+        if let Some(index) = genes.iter().position(|gene| !gene.is_finite()) {
+            return Err(NetworkError::NonFiniteWeight {
+                index,
+                value: genes[index],
+            });
+        }
+
+        let rules = genes
+            .split_off(LayerTopology::weights_len(&topology.layers))
+            .chunks_exact(4)
+            .map(|rule| [rule[0], rule[1], rule[2], rule[3]])
+            .collect();
+
+        let network = Network::try_from_weights(&topology.layers, genes)?;
+
+        Ok(Self {
+            topology: topology.clone(),
+            initial: network.clone(),
+            network,
+            rules,
+        })
+    }
+
+    /// Flattens this network into a genome: initial weights (in
+    /// `Network::weights()` order), followed by `A`, `B`, `C` and `D` of
+    /// each connection.
+    pub fn weights(&self) -> Vec<f32> {
+        let mut weights = self.initial.weights();
+
+        weights.extend(self.rules.iter().flatten());
+        weights
+    }
+
+    pub fn topology(&self) -> &PlasticTopology {
+        &self.topology
+    }
+
+    /// Network with the weights learned so far.
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    /// Propagates the inputs, then updates the weights according to how
+    /// active each connection's ends were.
+    pub fn propagate(&mut self, inputs: Vec<f32>) -> Vec<f32> {
+        self.propagate_into(&inputs, &mut Scratch::new()).to_vec()
+    }
+
+    /// Allocation-free version of `propagate()`.
+    pub fn propagate_into<'a>(&mut self, inputs: &[f32], scratch: &'a mut Scratch) -> &'a [f32] {
+        self.network.propagate_graph_into(inputs, scratch);
+        self.learn(&scratch.nodes, &mut scratch.front);
+
+        &scratch.nodes[scratch.nodes.len() - 1]
+    }
+
+    /// Like `Network::propagate_traced()`; learns just like `propagate()`.
+    pub fn propagate_traced(&mut self, inputs: Vec<f32>) -> Trace {
+        let trace = self.network.propagate_traced(inputs.clone(), &mut Scratch::new());
+        let groups = self.network.inputs.len();
+        let mut nodes = Vec::new();
+
+        self.network.split_inputs(&inputs, 1, &mut nodes);
+
+        for (idx, layer) in trace.layers.iter().enumerate() {
+            nodes[groups + idx].clone_from(&layer.post_activation);
+        }
+
+        self.learn(&nodes, &mut Vec::new());
+        trace
+    }
+
+    /// Evaluates many input vectors at once with the current weights,
+    /// without learning anything.
+    pub fn propagate_batch(&self, inputs: &[f32], batch: usize) -> Vec<f32> {
+        self.network.propagate_batch(inputs, batch)
+    }
+
+    /// Forgets everything learned so far, going back to the initial weights.
+    pub fn reset_state(&mut self) {
+        self.network.clone_from(&self.initial);
+    }
+
+    // Applies the rule to every (enabled) connection, given outputs of every
+    // input group and layer - as left by `Network::propagate_graph_into()`
+    fn learn(&mut self, nodes: &[Vec<f32>], inputs: &mut Vec<f32>) {
+        let PlasticTopology {
+            learning_rate,
+            weight_limit,
+            ..
+        } = self.topology;
+
+        let groups = self.network.inputs.len();
+        let mut rules = self.rules.iter();
+
+        for (idx, layer) in self.network.layers.iter_mut().enumerate() {
+            gather(nodes, &layer.sources, 1, inputs);
+
+            let outputs = &nodes[groups + idx];
+            let width = layer.inputs;
+
+            for ((row, mask), &y) in layer
+                .weights
+                .chunks_exact_mut(width)
+                .zip(layer.mask.chunks_exact(width))
+                .zip(outputs)
+            {
+                for ((weight, &enabled), &x) in row.iter_mut().zip(mask).zip(inputs.iter()) {
+                    let [a, b, c, d] = rules.next().unwrap();
+
+                    if enabled {
+                        let delta = learning_rate * (a * x * y + b * x + c * y + d);
+
+                        *weight = (*weight + delta).clamp(-weight_limit, weight_limit);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    // Single identity neuron, with given weight (no bias) and rule
+    fn neuron(weight: f32, rule: [f32; 4]) -> PlasticNetwork {
+        let topology = PlasticTopology::new(vec![
+            LayerTopology::new(1),
+            LayerTopology::new(1).with_activation(Activation::Identity),
+        ])
+        .with_learning_rate(0.1);
+
+        PlasticNetwork::from_weights(&topology, [0.0, weight].into_iter().chain(rule))
+    }
+
+    fn weight(network: &PlasticNetwork) -> f32 {
+        network.network().weights()[1]
+    }
+
+    mod propagate {
+        use super::*;
+
+        #[test]
+        fn hebbian_term() {
+            let mut network = neuron(0.5, [1.0, 0.0, 0.0, 0.0]);
+
+            // y = 0.5 * 2.0; dw = 0.1 * 2.0 * 1.0
+            approx::assert_relative_eq!(network.propagate(vec![2.0])[0], 1.0);
+            approx::assert_relative_eq!(weight(&network), 0.7);
+
+            approx::assert_relative_eq!(network.propagate(vec![2.0])[0], 1.4);
+        }
+
+        #[test]
+        fn other_terms() {
+            let mut network = neuron(0.5, [0.0, 1.0, 2.0, 3.0]);
+
+            // y = 1.0; dw = 0.1 * (2.0 + 2.0 * 1.0 + 3.0)
+            network.propagate(vec![2.0]);
+            approx::assert_relative_eq!(weight(&network), 1.2);
+        }
+
+        #[test]
+        fn respects_weight_limit() {
+            let mut network = neuron(0.5, [0.0, 0.0, 0.0, 100.0]);
+
+            network.propagate(vec![1.0]);
+            approx::assert_relative_eq!(weight(&network), 4.0);
+        }
+
+        #[test]
+        fn skips_disabled_connections() {
+            let mut network = neuron(0.5, [0.0, 0.0, 0.0, 1.0]);
+
+            network.network.set_mask([true, false]).unwrap();
+            network.propagate(vec![1.0]);
+
+            approx::assert_relative_eq!(weight(&network), 0.0);
+        }
+    }
+
+    mod propagate_traced {
+        use super::*;
+
+        #[test]
+        fn learns_like_propagate() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let topology = PlasticTopology::new(vec![
+                LayerTopology::new(3),
+                LayerTopology::new(4),
+                LayerTopology::new(2).with_activation(Activation::Tanh),
+            ])
+            .with_learning_rate(0.5);
+
+            let mut network = PlasticNetwork::random(&mut rng, &topology);
+            let mut traced = network.clone();
+
+            for inputs in [[0.1, 0.5, 0.9], [0.7, 0.0, 0.3], [0.2, 0.2, 0.2]] {
+                let expected = network.propagate(inputs.to_vec());
+                let trace = traced.propagate_traced(inputs.to_vec());
+
+                approx::assert_relative_eq!(trace.outputs(), expected.as_slice());
+            }
+
+            let expected = network.network().weights();
+            let actual = traced.network().weights();
+
+            approx::assert_relative_eq!(actual.as_slice(), expected.as_slice());
+        }
+    }
+
+    mod reset_state {
+        use super::*;
+
+        #[test]
+        fn restores_initial_weights() {
+            let mut network = neuron(0.5, [1.0, 0.0, 0.0, 0.0]);
+
+            network.propagate(vec![2.0]);
+            network.reset_state();
+
+            approx::assert_relative_eq!(weight(&network), 0.5);
+        }
+    }
+
+    mod weights {
+        use super::*;
+
+        #[test]
+        fn are_not_affected_by_learning() {
+            let mut network = neuron(0.5, [1.0, 2.0, 3.0, 4.0]);
+
+            network.propagate(vec![2.0]);
+
+            let actual = network.weights();
+            let expected = [0.0, 0.5, 1.0, 2.0, 3.0, 4.0];
+
+            approx::assert_relative_eq!(actual.as_slice(), expected.as_ref());
+        }
+
+        #[test]
+        fn len() {
+            let topology = PlasticTopology::new(vec![
+                LayerTopology::new(3),
+                LayerTopology::new(2),
+                LayerTopology::new(1),
+            ]);
+
+            // 6 + 2 connections, 2 + 1 biases
+            assert_eq!(topology.connections(), 8);
+            assert_eq!(topology.weights_len(), 11 + 4 * 8);
+        }
+    }
+
+    mod try_from_weights {
+        use super::*;
+
+        #[test]
+        fn rejects_recurrent_layers() {
+            let topology = PlasticTopology::new(vec![
+                LayerTopology::new(1),
+                LayerTopology::new(1).with_kind(LayerKind::Elman),
+            ]);
+
+            assert_eq!(
+                PlasticNetwork::try_from_weights(&topology, vec![0.0; 7]).unwrap_err(),
+                NetworkError::UnsupportedLayerKind {
+                    layer: 1,
+                    kind: LayerKind::Elman
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,401 @@
+use rand::{Rng, RngCore};
+
+use crate::*;
+
+/// Inference-only version of a `Network`, with weights squeezed into `i8`s -
+/// a quarter of the memory, and dot products computed on integers.
+///
+/// Each layer keeps a single scale factor, mapping its largest weight (by
+/// magnitude) onto 127; the layer's inputs get quantized the same way on
+/// the fly, one input vector at a time. Biases stay `f32`, since there's
+/// just one per neuron.
+///
+/// Only dense layers are supported. It's all plain Rust, so it works just
+/// as well in WebAssembly.
+///
+/// Quantizing the inputs of every layer isn't free - with just a few inputs
+/// per neuron (like the default brain's nine) it costs more than the integer
+/// dot products save, and the float `Network` is faster; from a few dozen
+/// inputs on, this one is (see `benches/propagate.rs`).
+#[derive(Clone, Debug)]
+pub struct QuantizedNetwork {
+    // Widths of the input groups, like in `Network`
+    inputs: Vec<usize>,
+    layers: Vec<QuantizedLayer>,
+
+    // See `Network::is_chain()`
+    chain: bool,
+}
+
+// Number of values `dot()` multiplies at once - rows of weights (and the
+// quantized inputs) are padded with zeros to a multiple of it
+const LANES: usize = 16;
+
+#[derive(Clone, Debug)]
+struct QuantizedLayer {
+    inputs: usize,
+    sources: Vec<usize>,
+
+    // Value of a single step of `weights`
+    scale: f32,
+
+    // Row-major like in `Layer`, but with rows padded to `width()`
+    weights: Vec<i8>,
+    biases: Vec<f32>,
+    activation: Activation,
+}
+
+/// How far `QuantizedNetwork`'s outputs are from the original network's -
+/// see `QuantizedNetwork::error_report()`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QuantizationReport {
+    /// Number of input vectors the networks were compared on
+    pub samples: usize,
+
+    /// Largest absolute difference of a single output
+    pub max_error: f32,
+
+    /// Mean absolute difference over all of the outputs
+    pub mean_error: f32,
+
+    /// Root-mean-square difference over all of the outputs
+    pub rms_error: f32,
+}
+
+impl Network {
+    /// Converts this network into a `QuantizedNetwork`; fails for networks
+    /// with layers other than dense ones.
+    pub fn quantize(&self) -> Result<QuantizedNetwork, NetworkError> {
+        let groups = self.inputs.len();
+
+        let layers = self
+            .layers
+            .iter()
+            .enumerate()
+            .map(|(idx, layer)| {
+                if layer.kind != LayerKind::Dense {
+                    return Err(NetworkError::UnsupportedLayerKind {
+                        layer: groups + idx,
+                        kind: layer.kind,
+                    });
+                }
+
+                let mut steps = Vec::with_capacity(layer.weights.len());
+                let scale = quantize(&layer.weights, &mut steps);
+                let padding = layer.inputs.next_multiple_of(LANES) - layer.inputs;
+
+                let weights = steps
+                    .chunks_exact(layer.inputs)
+                    .flat_map(|row| {
+                        row.iter()
+                            .map(|&step| step as i8)
+                            .chain(std::iter::repeat_n(0, padding))
+                    })
+                    .collect();
+
+                Ok(QuantizedLayer {
+                    inputs: layer.inputs,
+                    sources: layer.sources.clone(),
+                    scale,
+                    weights,
+                    biases: layer.biases.clone(),
+                    activation: layer.activation.clone(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(QuantizedNetwork {
+            inputs: self.inputs.clone(),
+            layers,
+            chain: self.is_chain(),
+        })
+    }
+}
+
+impl QuantizedNetwork {
+    pub fn propagate(&self, inputs: Vec<f32>) -> Vec<f32> {
+        if !self.chain {
+            return self.propagate_into(&inputs, &mut Scratch::new()).to_vec();
+        }
+
+        // Same as `Network::propagate()`, reusing the inputs' allocation
+        let mut scratch = Scratch { back: inputs, ..Scratch::new() };
+        let Scratch { front, back, quantized, .. } = &mut scratch;
+
+        for layer in &self.layers {
+            layer.propagate_batch_into(back, 1, quantized, front);
+            std::mem::swap(front, back);
+        }
+
+        scratch.back
+    }
+
+    /// Allocation-free version of `propagate()`, see
+    /// `Network::propagate_into()`.
+    pub fn propagate_into<'a>(&self, inputs: &[f32], scratch: &'a mut Scratch) -> &'a [f32] {
+        self.propagate_batch_into(inputs, 1, scratch)
+    }
+
+    /// Propagates `batch` input vectors, laid out one after another (just
+    /// like for `Network::propagate_batch()`).
+    pub fn propagate_batch(&self, inputs: &[f32], batch: usize) -> Vec<f32> {
+        self.propagate_batch_into(inputs, batch, &mut Scratch::new()).to_vec()
+    }
+
+    /// Allocation-free version of `propagate_batch()`.
+    pub fn propagate_batch_into<'a>(
+        &self,
+        inputs: &[f32],
+        batch: usize,
+        scratch: &'a mut Scratch,
+    ) -> &'a [f32] {
+        let groups = self.inputs.len();
+
+        if !self.chain {
+            let Scratch { front, nodes, quantized, .. } = scratch;
+
+            split_inputs(&self.inputs, self.layers.len(), inputs, batch, nodes);
+
+            for (idx, layer) in self.layers.iter().enumerate() {
+                gather(nodes, &layer.sources, batch, front);
+                layer.propagate_batch_into(front, batch, quantized, &mut nodes[groups + idx]);
+            }
+
+            return &nodes[groups + self.layers.len() - 1];
+        }
+
+        let Scratch { front, back, quantized, .. } = scratch;
+        let (first, rest) = self.layers.split_first().expect("network has no layers");
+
+        first.propagate_batch_into(inputs, batch, quantized, front);
+
+        for layer in rest {
+            layer.propagate_batch_into(front, batch, quantized, back);
+            std::mem::swap(front, back);
+        }
+
+        front
+    }
+
+    /// Scale factor of each layer, i.e. the value a weight of `1` stands for.
+    pub fn scales(&self) -> Vec<f32> {
+        self.layers.iter().map(|layer| layer.scale).collect()
+    }
+
+    /// Compares this network with the one it's been quantized from, on
+    /// `samples` random input vectors drawn uniformly from <-1, 1>.
+    pub fn error_report(&self, network: &Network, rng: &mut dyn RngCore, samples: usize) -> QuantizationReport {
+        let inputs: Vec<f32> = (0..samples * network.input_width())
+            .map(|_| rng.gen_range(-1.0..=1.0))
+            .collect();
+
+        let expected = network.propagate_batch(&inputs, samples);
+        let actual = self.propagate_batch(&inputs, samples);
+
+        assert_eq!(actual.len(), expected.len());
+
+        if actual.is_empty() {
+            return QuantizationReport::default();
+        }
+
+        let errors: Vec<f32> = actual
+            .iter()
+            .zip(&expected)
+            .map(|(actual, expected)| (actual - expected).abs())
+            .collect();
+
+        let len = errors.len() as f32;
+
+        QuantizationReport {
+            samples,
+            max_error: errors.iter().fold(0.0, |max, &error| error.max(max)),
+            mean_error: errors.iter().sum::<f32>() / len,
+            rms_error: (errors.iter().map(|error| error * error).sum::<f32>() / len).sqrt(),
+        }
+    }
+}
+
+impl QuantizedLayer {
+    // Like `Layer::propagate_batch_into()`; `quantized` is where each of the
+    // input vectors gets quantized into
+    fn propagate_batch_into(
+        &self,
+        inputs: &[f32],
+        batch: usize,
+        quantized: &mut Vec<i16>,
+        outputs: &mut Vec<f32>,
+    ) {
+        assert_eq!(inputs.len(), batch * self.inputs);
+
+        outputs.clear();
+
+        for inputs in inputs.chunks_exact(self.inputs).take(batch) {
+            let scale = self.scale * quantize(inputs, quantized);
+
+            quantized.resize(self.width(), 0);
+
+            outputs.extend(self.weights.chunks_exact(self.width()).zip(&self.biases).map(
+                |(row, bias)| self.activation.apply(bias + dot(row, quantized) as f32 * scale),
+            ));
+        }
+    }
+
+    fn width(&self) -> usize {
+        self.inputs.next_multiple_of(LANES)
+    }
+}
+
+// Multiplies `LANES` values at once, keeping a separate sum for each lane -
+// simple enough for the compiler to turn into SIMD instructions, wherever
+// the target has got them
+fn dot(weights: &[i8], inputs: &[i16]) -> i32 {
+    let mut sums = [0; LANES];
+
+    for (weights, inputs) in weights.chunks_exact(LANES).zip(inputs.chunks_exact(LANES)) {
+        for ((sum, &weight), &input) in sums.iter_mut().zip(weights).zip(inputs) {
+            *sum += weight as i32 * input as i32;
+        }
+    }
+
+    sums.iter().sum()
+}
+
+// Symmetric quantization - maps <-max, max> onto <-127, 127>, returning the
+// value of a single step.
+//
+// Steps fit into `i8`, but they're returned as `i16` - so that, of the two
+// sides of `dot()`, just the weights need widening.
+fn quantize(values: &[f32], into: &mut Vec<i16>) -> f32 {
+    let max = values
+        .iter()
+        .map(|value| value.abs())
+        .fold(0.0_f32, |max, value| if value > max { value } else { max });
+
+    let (scale, steps) = if max > 0.0 { (max / 127.0, 127.0 / max) } else { (1.0, 1.0) };
+
+    into.resize(values.len(), 0);
+
+    // Rounds half away from zero, just like `f32::round()` - which (unlike
+    // the truncating cast) isn't a single instruction on every target, and
+    // this runs for every input of every layer
+    for (into, &value) in into.iter_mut().zip(values) {
+        *into = (value * steps + 0.5_f32.copysign(value)) as i16;
+    }
+
+    scale
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    fn network(rng: &mut ChaCha8Rng) -> Network {
+        Network::random(
+            rng,
+            &[
+                LayerTopology::new(9),
+                LayerTopology::new(18),
+                LayerTopology::new(2).with_activation(Activation::Tanh),
+            ],
+        )
+    }
+
+    mod quantize {
+        use super::*;
+
+        #[test]
+        fn scales() {
+            let network = Network::from_weights(
+                &[LayerTopology::new(2), LayerTopology::new(1)],
+                vec![5.0, 0.5, -2.54],
+            );
+
+            // Biases don't count
+            let actual = network.quantize().unwrap().scales();
+            approx::assert_relative_eq!(actual.as_slice(), [0.02].as_ref());
+        }
+
+        #[test]
+        fn rejects_recurrent_layers() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let network = Network::random(
+                &mut rng,
+                &[LayerTopology::new(2), LayerTopology::new(2).with_kind(LayerKind::Elman)],
+            );
+
+            assert_eq!(
+                network.quantize().unwrap_err(),
+                NetworkError::UnsupportedLayerKind { layer: 1, kind: LayerKind::Elman },
+            );
+        }
+    }
+
+    mod propagate {
+        use super::*;
+
+        #[test]
+        fn test() {
+            // relu(0.25 + 1.0 * x0 - 0.5 * x1), where -0.5 is quantized
+            // into -64 steps of 1/127
+            let network = Network::from_weights(
+                &[LayerTopology::new(2), LayerTopology::new(1)],
+                vec![0.25, 1.0, -0.5],
+            );
+
+            let actual = network.quantize().unwrap().propagate(vec![1.0, -1.0]);
+            let expected = [0.25 + 1.0 + 64.0 / 127.0];
+
+            approx::assert_relative_eq!(actual.as_slice(), expected.as_ref());
+        }
+
+        #[test]
+        fn with_skip_connections() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let network = Network::random(
+                &mut rng,
+                &[
+                    LayerTopology::new(3),
+                    LayerTopology::input(1),
+                    LayerTopology::new(4).with_sources([0]),
+                    LayerTopology::new(1).with_sources([1, 2]),
+                ],
+            );
+
+            let quantized = network.quantize().unwrap();
+            let inputs = [0.1, 0.5, 0.9, 0.3];
+
+            let actual = quantized.propagate(inputs.to_vec());
+            let expected = network.propagate_batch(&inputs, 1);
+
+            approx::assert_relative_eq!(actual.as_slice(), expected.as_slice(), epsilon = 0.05);
+        }
+    }
+
+    mod error_report {
+        use super::*;
+
+        #[test]
+        fn test() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let network = network(&mut rng);
+            let report = network.quantize().unwrap().error_report(&network, &mut rng, 100);
+
+            assert_eq!(report.samples, 100);
+            assert!(report.max_error < 0.1, "{:?}", report);
+            assert!(report.mean_error <= report.rms_error, "{:?}", report);
+            assert!(report.rms_error <= report.max_error, "{:?}", report);
+        }
+
+        #[test]
+        fn no_samples() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let network = network(&mut rng);
+            let report = network.quantize().unwrap().error_report(&network, &mut rng, 0);
+
+            assert_eq!(report, QuantizationReport::default());
+        }
+    }
+}
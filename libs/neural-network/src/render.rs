@@ -0,0 +1,485 @@
+use std::fmt::Write;
+
+use crate::*;
+
+const POSITIVE: &str = "#2166ac";
+const NEGATIVE: &str = "#b2182b";
+
+// SVG layout, in pixels
+const MARGIN: f32 = 40.0;
+const LAYER_SPACING: f32 = 160.0;
+const NEURON_SPACING: f32 = 32.0;
+const NEURON_RADIUS: f32 = 9.0;
+
+/// Settings for `Network::to_dot()` and `Network::to_svg()`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RenderOptions {
+    /// Connections with weights closer to zero than this are left out,
+    /// which makes larger networks readable
+    pub prune_below: f32,
+}
+
+impl RenderOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_pruning(mut self, prune_below: f32) -> Self {
+        assert!(prune_below >= 0.0);
+
+        self.prune_below = prune_below;
+        self
+    }
+}
+
+// Single connection between two neurons, each identified by (layer,
+// neuron) - layers being numbered like in the topology, input groups first
+struct Edge {
+    from: (usize, usize),
+    to: (usize, usize),
+    weight: f32,
+    recurrent: bool,
+}
+
+impl Network {
+    /// Renders this network as a Graphviz graph, one cluster per layer.
+    ///
+    /// Blue edges stand for positive weights and red ones for negative
+    /// weights, with the larger magnitudes being thicker and more opaque;
+    /// recurrent connections are dashed. GRU layers show their candidate
+    /// weights only.
+    pub fn to_dot(&self, options: &RenderOptions) -> String {
+        let edges = self.edges(options);
+        let max_weight = max_weight(&edges);
+        let mut dot = String::new();
+
+        dot.push_str("digraph network {\n");
+        dot.push_str("    rankdir=LR;\n");
+        dot.push_str("    node [shape=circle, label=\"\", width=0.3];\n");
+
+        for (layer, neurons) in self.widths().into_iter().enumerate() {
+            writeln!(dot, "\n    subgraph cluster_{} {{", layer).unwrap();
+            writeln!(dot, "        label=\"{}\";", self.layer_label(layer)).unwrap();
+            dot.push_str("        color=\"#cccccc\";\n");
+
+            for neuron in 0..neurons {
+                match self.bias(layer, neuron) {
+                    Some(bias) => writeln!(
+                        dot,
+                        "        l{}n{} [tooltip=\"bias {:.3}\"];",
+                        layer, neuron, bias
+                    ),
+                    None => writeln!(dot, "        l{}n{};", layer, neuron),
+                }
+                .unwrap();
+            }
+
+            dot.push_str("    }\n");
+        }
+
+        dot.push('\n');
+
+        for edge in &edges {
+            let (colour, width, opacity) = stroke(edge.weight, max_weight);
+
+            writeln!(
+                dot,
+                "    l{}n{} -> l{}n{} [color=\"{}{:02x}\", penwidth={:.2}, tooltip=\"{:.3}\"{}];",
+                edge.from.0,
+                edge.from.1,
+                edge.to.0,
+                edge.to.1,
+                colour,
+                (opacity * 255.0).round() as u8,
+                width,
+                edge.weight,
+                if edge.recurrent {
+                    ", style=dashed, constraint=false"
+                } else {
+                    ""
+                },
+            )
+            .unwrap();
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Renders this network as a standalone SVG image, using the same
+    /// encoding as `to_dot()` - no Graphviz needed.
+    pub fn to_svg(&self, options: &RenderOptions) -> String {
+        let edges = self.edges(options);
+        let max_weight = max_weight(&edges);
+        let widths = self.widths();
+        let tallest = widths.iter().copied().max().unwrap_or(0);
+
+        let width = 2.0 * MARGIN + (widths.len() - 1) as f32 * LAYER_SPACING;
+        let height = 2.0 * MARGIN + tallest as f32 * NEURON_SPACING;
+
+        // Each layer gets centered vertically
+        let position = |(layer, neuron): (usize, usize)| {
+            let offset = (tallest - widths[layer]) as f32 * NEURON_SPACING / 2.0;
+
+            (
+                MARGIN + layer as f32 * LAYER_SPACING,
+                MARGIN + offset + (neuron as f32 + 0.5) * NEURON_SPACING,
+            )
+        };
+
+        let mut svg = String::new();
+
+        writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">",
+            w = width,
+            h = height
+        )
+        .unwrap();
+
+        svg.push_str("  <rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n");
+
+        for edge in &edges {
+            let (colour, stroke_width, opacity) = stroke(edge.weight, max_weight);
+            let (x1, y1) = position(edge.from);
+            let (x2, y2) = position(edge.to);
+
+            let style = format!(
+                "stroke=\"{}\" stroke-width=\"{:.2}\" stroke-opacity=\"{:.2}\"",
+                colour, stroke_width, opacity
+            );
+
+            if edge.recurrent {
+                // Arc bulging to the right of the layer, so that it doesn't
+                // overlap with the neurons in between
+                let bulge = x1 + NEURON_RADIUS + 0.5 * (y2 - y1).abs().max(NEURON_SPACING);
+
+                writeln!(
+                    svg,
+                    "  <path d=\"M {:.1} {:.1} Q {:.1} {:.1} {:.1} {:.1}\" fill=\"none\" stroke-dasharray=\"4 2\" {}><title>{:.3}</title></path>",
+                    x1, y1, bulge, (y1 + y2) / 2.0, x2, y2, style, edge.weight
+                )
+                .unwrap();
+            } else {
+                writeln!(
+                    svg,
+                    "  <line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" {}><title>{:.3}</title></line>",
+                    x1, y1, x2, y2, style, edge.weight
+                )
+                .unwrap();
+            }
+        }
+
+        for (layer, neurons) in widths.iter().enumerate() {
+            let (x, _) = position((layer, 0));
+
+            writeln!(
+                svg,
+                "  <text x=\"{:.1}\" y=\"{:.1}\" font-family=\"sans-serif\" font-size=\"11\" text-anchor=\"middle\">{}</text>",
+                x,
+                MARGIN / 2.0,
+                escape(&self.layer_label(layer))
+            )
+            .unwrap();
+
+            for neuron in 0..*neurons {
+                let (x, y) = position((layer, neuron));
+
+                write!(
+                    svg,
+                    "  <circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{}\" fill=\"#f7f7f7\" stroke=\"#333333\">",
+                    x, y, NEURON_RADIUS
+                )
+                .unwrap();
+
+                if let Some(bias) = self.bias(layer, neuron) {
+                    write!(svg, "<title>bias {:.3}</title>", bias).unwrap();
+                }
+
+                svg.push_str("</circle>\n");
+            }
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    fn edges(&self, options: &RenderOptions) -> Vec<Edge> {
+        let widths = self.widths();
+        let mut edges = Vec::new();
+
+        for (idx, layer) in self.layers.iter().enumerate() {
+            let node = self.inputs.len() + idx;
+
+            // Where each of the layer's inputs comes from
+            let origins: Vec<_> = layer
+                .sources
+                .iter()
+                .flat_map(|&source| (0..widths[source]).map(move |neuron| (source, neuron)))
+                .collect();
+
+            // Each of a convolution's outputs is drawn with its own copy of
+            // the filter, showing which cells it sees
+            if let LayerKind::CircularConv { .. } = layer.kind {
+                let rows = layer.rows().zip(layer.mask.chunks_exact(layer.row_width()));
+
+                for (filter, (row, mask)) in rows.enumerate() {
+                    for cell in 0..layer.inputs {
+                        for (tap, &weight) in row.iter().enumerate() {
+                            if !mask[tap] {
+                                continue;
+                            }
+
+                            edges.push(Edge {
+                                from: origins[layer.conv_input(cell, tap)],
+                                to: (node, filter * layer.inputs + cell),
+                                weight,
+                                recurrent: false,
+                            });
+                        }
+                    }
+                }
+
+                continue;
+            }
+
+            // GRU's candidate rows come last
+            let skip = (layer.kind.gates() - 1) * layer.neurons;
+            let rows = layer
+                .rows()
+                .zip(layer.mask.chunks_exact(layer.row_width()))
+                .skip(skip);
+
+            for (neuron, (row, mask)) in rows.enumerate() {
+                let (input_weights, recurrent_weights) = row.split_at(layer.inputs);
+                let (input_mask, recurrent_mask) = mask.split_at(layer.inputs);
+
+                for (input, &weight) in input_weights.iter().enumerate() {
+                    // Disabled connections are not there at all
+                    if !input_mask[input] {
+                        continue;
+                    }
+
+                    edges.push(Edge {
+                        from: origins[input],
+                        to: (node, neuron),
+                        weight,
+                        recurrent: false,
+                    });
+                }
+
+                for (source, &weight) in recurrent_weights.iter().enumerate() {
+                    if !recurrent_mask[source] {
+                        continue;
+                    }
+
+                    edges.push(Edge {
+                        from: (node, source),
+                        to: (node, neuron),
+                        weight,
+                        recurrent: true,
+                    });
+                }
+            }
+        }
+
+        edges.retain(|edge| edge.weight.abs() >= options.prune_below);
+        edges
+    }
+
+    // Number of neurons per layer, input groups included
+    fn widths(&self) -> Vec<usize> {
+        self.inputs
+            .iter()
+            .copied()
+            .chain(self.layers.iter().map(|layer| layer.neurons))
+            .collect()
+    }
+
+    fn layer_label(&self, layer: usize) -> String {
+        match self.inputs.len() {
+            1 if layer == 0 => return "input".into(),
+            groups if layer < groups => return format!("input {}", layer),
+            _ => (),
+        }
+
+        let layer = &self.layers[layer - self.inputs.len()];
+
+        let activation = match &layer.activation {
+            Activation::Custom(custom) => custom.name().to_string(),
+            activation => format!("{:?}", activation),
+        };
+
+        match layer.kind {
+            LayerKind::Dense => activation,
+            LayerKind::CircularConv { kernel } => format!("CircularConv({}) {}", kernel, activation),
+            kind => format!("{:?} {}", kind, activation),
+        }
+    }
+
+    fn bias(&self, layer: usize, neuron: usize) -> Option<f32> {
+        let layer = self.layers.get(layer.checked_sub(self.inputs.len())?)?;
+
+        // GRU's candidate biases come last, just like its rows, while
+        // convolutions share a bias per filter
+        match layer.kind {
+            LayerKind::CircularConv { .. } => Some(layer.biases[neuron / layer.inputs]),
+            kind => Some(layer.biases[(kind.gates() - 1) * layer.neurons + neuron]),
+        }
+    }
+}
+
+fn max_weight(edges: &[Edge]) -> f32 {
+    edges
+        .iter()
+        .map(|edge| edge.weight.abs())
+        .fold(0.0, f32::max)
+}
+
+// Colour, width and opacity for an edge of given weight
+fn stroke(weight: f32, max_weight: f32) -> (&'static str, f32, f32) {
+    let magnitude = if max_weight > 0.0 {
+        weight.abs() / max_weight
+    } else {
+        0.0
+    };
+
+    let colour = if weight >= 0.0 { POSITIVE } else { NEGATIVE };
+
+    (colour, 0.5 + 3.5 * magnitude, 0.25 + 0.75 * magnitude)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2 inputs, 2 hidden neurons, 1 output
+    fn network() -> Network {
+        Network::from_weights(
+            &[
+                LayerTopology::new(2),
+                LayerTopology::new(2),
+                LayerTopology::new(1).with_activation(Activation::Tanh),
+            ],
+            vec![0.1, 1.0, -0.05, 0.2, -2.0, 0.01, 0.0, 0.5, -0.5],
+        )
+    }
+
+    mod to_dot {
+        use super::*;
+
+        #[test]
+        fn test() {
+            let dot = network().to_dot(&RenderOptions::new());
+
+            assert!(dot.starts_with("digraph network {\n"));
+            assert!(dot.contains("label=\"Tanh\";"));
+            assert!(dot.contains("l1n0 [tooltip=\"bias 0.100\"];"));
+            assert_eq!(dot.matches(" -> ").count(), 6);
+
+            // -2.0 is the heaviest weight
+            assert!(dot.contains("l0n0 -> l1n1 [color=\"#b2182bff\", penwidth=4.00"));
+            assert!(dot.contains("l1n0 -> l2n0 [color=\"#2166ac70\", penwidth=1.38"));
+        }
+
+        #[test]
+        fn prunes_weights() {
+            let dot = network().to_dot(&RenderOptions::new().with_pruning(0.1));
+
+            assert_eq!(dot.matches(" -> ").count(), 4);
+            assert!(!dot.contains("l0n1 -> l1n0"));
+        }
+
+        #[test]
+        fn skips_disabled_connections() {
+            let mut network = network();
+
+            network.prune(0.1);
+
+            assert_eq!(network.to_dot(&RenderOptions::new()).matches(" -> ").count(), 4);
+        }
+
+        #[test]
+        fn marks_recurrent_connections() {
+            let network = Network::from_weights(
+                &[
+                    LayerTopology::new(1),
+                    LayerTopology::new(1).with_kind(LayerKind::Elman),
+                ],
+                vec![0.0, 1.0, 0.5],
+            );
+
+            let dot = network.to_dot(&RenderOptions::new());
+
+            assert!(dot.contains("label=\"Elman ReLU\";"));
+            assert!(dot.contains("l1n0 -> l1n0 [color=\"#2166ac9f\", penwidth=2.25, tooltip=\"0.500\", style=dashed"));
+        }
+
+        #[test]
+        fn draws_skip_connections() {
+            let network = Network::from_weights(
+                &[
+                    LayerTopology::new(1),
+                    LayerTopology::input(1),
+                    LayerTopology::new(1),
+                    LayerTopology::new(1).with_sources([0, 2]),
+                ],
+                vec![0.0, 1.0, 0.0, 0.5, -0.5],
+            );
+
+            let dot = network.to_dot(&RenderOptions::new());
+
+            assert!(dot.contains("label=\"input 0\";"));
+            assert!(dot.contains("label=\"input 1\";"));
+            assert!(dot.contains("l1n0 -> l2n0 "));
+            assert!(dot.contains("l0n0 -> l3n0 "));
+            assert!(dot.contains("l2n0 -> l3n0 "));
+            assert_eq!(dot.matches(" -> ").count(), 3);
+        }
+
+        #[test]
+        fn draws_convolutions() {
+            let network = Network::from_weights(
+                &[
+                    LayerTopology::new(3),
+                    LayerTopology::new(3).with_kind(LayerKind::CircularConv { kernel: 2 }),
+                ],
+                vec![0.0, 1.0, -1.0],
+            );
+
+            let dot = network.to_dot(&RenderOptions::new());
+
+            assert!(dot.contains("label=\"CircularConv(2) ReLU\";"));
+            assert!(dot.contains("l0n2 -> l1n0 [color=\"#2166ac"));
+            assert!(dot.contains("l0n0 -> l1n0 [color=\"#b2182b"));
+            assert_eq!(dot.matches(" -> ").count(), 6);
+        }
+    }
+
+    mod to_svg {
+        use super::*;
+
+        #[test]
+        fn test() {
+            let svg = network().to_svg(&RenderOptions::new());
+
+            assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"400\" height=\"144\""));
+            assert!(svg.ends_with("</svg>\n"));
+            assert_eq!(svg.matches("<circle").count(), 5);
+            assert_eq!(svg.matches("<line").count(), 6);
+        }
+
+        #[test]
+        fn prunes_weights() {
+            let svg = network().to_svg(&RenderOptions::new().with_pruning(0.1));
+
+            assert_eq!(svg.matches("<line").count(), 4);
+        }
+    }
+}
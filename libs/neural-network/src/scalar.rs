@@ -0,0 +1,87 @@
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// Number type a `Network` computes with.
+///
+/// Floats are the natural choice, but their transcendental functions (like
+/// `exp()` or `tanh()`) are not guaranteed to yield the very same bits on
+/// every platform - when that matters (e.g. to replay a simulation natively
+/// and in the browser), use `Fixed` instead.
+pub trait Scalar:
+    Copy
+    + Default
+    + PartialOrd
+    + fmt::Debug
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + Sum
+    + Send
+    + Sync
+    + 'static
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    /// Converts from `f64`, rounding to the nearest representable value.
+    fn from_f64(value: f64) -> Self;
+
+    fn to_f64(self) -> f64;
+
+    fn from_f32(value: f32) -> Self {
+        Self::from_f64(value as f64)
+    }
+
+    fn to_f32(self) -> f32 {
+        self.to_f64() as f32
+    }
+
+    fn abs(self) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn exp(self) -> Self;
+    fn tanh(self) -> Self;
+    fn is_finite(self) -> bool;
+}
+
+macro_rules! impl_float_scalar {
+    ($ty:ty) => {
+        impl Scalar for $ty {
+            const ZERO: Self = 0.0;
+            const ONE: Self = 1.0;
+
+            fn from_f64(value: f64) -> Self {
+                value as _
+            }
+
+            fn to_f64(self) -> f64 {
+                self as _
+            }
+
+            fn abs(self) -> Self {
+                <$ty>::abs(self)
+            }
+
+            fn max(self, other: Self) -> Self {
+                <$ty>::max(self, other)
+            }
+
+            fn exp(self) -> Self {
+                <$ty>::exp(self)
+            }
+
+            fn tanh(self) -> Self {
+                <$ty>::tanh(self)
+            }
+
+            fn is_finite(self) -> bool {
+                <$ty>::is_finite(self)
+            }
+        }
+    };
+}
+
+impl_float_scalar!(f32);
+impl_float_scalar!(f64);
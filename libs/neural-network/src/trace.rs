@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+/// Everything a network computed for a single input, layer by layer - see
+/// `Network::propagate_traced()`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Trace {
+    pub inputs: Vec<f32>,
+    pub layers: Vec<LayerTrace>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LayerTrace {
+    /// Weighted sums (plus biases) the layer's activation got applied to;
+    /// for GRU layers these belong to the candidate state
+    pub pre_activation: Vec<f32>,
+
+    /// The layer's outputs
+    pub post_activation: Vec<f32>,
+}
+
+impl Trace {
+    /// Outputs of the last layer, i.e. what `propagate()` would've returned.
+    pub fn outputs(&self) -> &[f32] {
+        self.layers
+            .last()
+            .map(|layer| layer.post_activation.as_slice())
+            .unwrap_or(&self.inputs)
+    }
+}
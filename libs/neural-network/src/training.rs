@@ -0,0 +1,486 @@
+use crate::*;
+
+/// Updates network's parameters given their gradients.
+///
+/// Both slices are laid out in `Network::weights()` order; stateful
+/// optimizers (like `Adam`) may assume that they get called with the same
+/// network over and over again.
+pub trait Optimizer {
+    fn step(&mut self, params: &mut [f32], gradients: &[f32]);
+}
+
+/// Plain stochastic gradient descent.
+#[derive(Clone, Debug)]
+pub struct Sgd {
+    learning_rate: f32,
+}
+
+impl Sgd {
+    pub fn new(learning_rate: f32) -> Self {
+        assert!(learning_rate > 0.0);
+
+        Self { learning_rate }
+    }
+}
+
+impl Optimizer for Sgd {
+    fn step(&mut self, params: &mut [f32], gradients: &[f32]) {
+        for (param, gradient) in params.iter_mut().zip(gradients) {
+            *param -= self.learning_rate * gradient;
+        }
+    }
+}
+
+/// Adam - gradient descent with per-parameter step sizes, adapted from
+/// running averages of gradients and their squares.
+#[derive(Clone, Debug)]
+pub struct Adam {
+    learning_rate: f32,
+    beta1: f32,
+    beta2: f32,
+    epsilon: f32,
+
+    // Number of steps taken so far, for bias correction
+    steps: i32,
+    means: Vec<f32>,
+    variances: Vec<f32>,
+}
+
+impl Adam {
+    /// Creates Adam with the usual `beta1 = 0.9` and `beta2 = 0.999`.
+    pub fn new(learning_rate: f32) -> Self {
+        assert!(learning_rate > 0.0);
+
+        Self {
+            learning_rate,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+            steps: 0,
+            means: Vec::new(),
+            variances: Vec::new(),
+        }
+    }
+
+    pub fn with_betas(mut self, beta1: f32, beta2: f32) -> Self {
+        assert!((0.0..1.0).contains(&beta1));
+        assert!((0.0..1.0).contains(&beta2));
+
+        self.beta1 = beta1;
+        self.beta2 = beta2;
+        self
+    }
+}
+
+impl Optimizer for Adam {
+    fn step(&mut self, params: &mut [f32], gradients: &[f32]) {
+        if self.means.len() != params.len() {
+            self.steps = 0;
+            self.means = vec![0.0; params.len()];
+            self.variances = vec![0.0; params.len()];
+        }
+
+        self.steps += 1;
+
+        let mean_correction = 1.0 - self.beta1.powi(self.steps);
+        let variance_correction = 1.0 - self.beta2.powi(self.steps);
+
+        for (((param, gradient), mean), variance) in params
+            .iter_mut()
+            .zip(gradients)
+            .zip(&mut self.means)
+            .zip(&mut self.variances)
+        {
+            *mean = self.beta1 * *mean + (1.0 - self.beta1) * gradient;
+            *variance = self.beta2 * *variance + (1.0 - self.beta2) * gradient * gradient;
+
+            let mean = *mean / mean_correction;
+            let variance = *variance / variance_correction;
+
+            *param -= self.learning_rate * mean / (variance.sqrt() + self.epsilon);
+        }
+    }
+}
+
+impl Network {
+    /// Mean squared error of the network's outputs over given samples, laid
+    /// out one after another (just like for `propagate_batch()`).
+    pub fn loss(&self, inputs: &[f32], targets: &[f32]) -> f32 {
+        let outputs = self.propagate_batch(inputs, inputs.len() / self.input_width());
+
+        assert_eq!(outputs.len(), targets.len());
+
+        mse(&outputs, targets)
+    }
+
+    /// Goes through all given samples once, in mini-batches of (at most)
+    /// `batch_size` samples, updating the network after each of them by
+    /// backpropagating the mean squared error; returns the average loss
+    /// over the whole epoch (measured before each update).
+    ///
+    /// Samples are visited in the given order - shuffle them between epochs
+    /// if needed. Only dense and convolutional layers can be trained.
+    pub fn train_epoch(
+        &mut self,
+        inputs: &[f32],
+        targets: &[f32],
+        batch_size: usize,
+        optimizer: &mut dyn Optimizer,
+    ) -> f32 {
+        assert!(batch_size > 0);
+        assert!(!self.is_recurrent(), "recurrent layers cannot be trained");
+
+        let (input_len, output_len) = (self.input_width(), self.outputs());
+        let samples = inputs.len() / input_len;
+
+        assert_eq!(inputs.len(), samples * input_len);
+        assert_eq!(targets.len(), samples * output_len);
+
+        let mut params = self.weights();
+        let mut loss = 0.0;
+
+        for (inputs, targets) in inputs
+            .chunks(batch_size * input_len)
+            .zip(targets.chunks(batch_size * output_len))
+        {
+            let batch = inputs.len() / input_len;
+            let (batch_loss, gradients) = self.backpropagate(inputs, targets, batch);
+
+            optimizer.step(&mut params, &gradients);
+            self.set_params(&params);
+
+            loss += batch_loss * batch as f32;
+        }
+
+        loss / samples as f32
+    }
+
+    fn outputs(&self) -> usize {
+        self.layers[self.layers.len() - 1].neurons
+    }
+
+    // Returns loss over given batch and its gradient with respect to every
+    // parameter, in `weights()` order
+    fn backpropagate(&self, inputs: &[f32], targets: &[f32], batch: usize) -> (f32, Vec<f32>) {
+        let groups = self.inputs.len();
+
+        // Forward pass, remembering each layer's weighted sums (needed for
+        // activations' derivatives) and every node's outputs (needed for
+        // weights')
+        let mut nodes = Vec::new();
+        let mut gathered = Vec::new();
+        let mut sums = Vec::with_capacity(self.layers.len());
+
+        self.split_inputs(inputs, batch, &mut nodes);
+
+        for (idx, layer) in self.layers.iter().enumerate() {
+            gather(&nodes, &layer.sources, batch, &mut gathered);
+
+            let mut layer_sums = Vec::with_capacity(batch * layer.neurons);
+            let mut outputs = Vec::new();
+
+            for inputs in gathered.chunks_exact(layer.inputs) {
+                if let LayerKind::CircularConv { .. } = layer.kind {
+                    layer.propagate_conv(inputs, &mut outputs, Some(&mut layer_sums));
+                    continue;
+                }
+
+                layer_sums.extend(
+                    layer
+                        .rows()
+                        .zip(&layer.biases)
+                        .map(|(row, bias)| bias + dot(row, inputs)),
+                );
+            }
+
+            nodes[groups + idx] = layer_sums.iter().map(|&sum| layer.activation.apply(sum)).collect();
+            sums.push(layer_sums);
+        }
+
+        let last = self.layers.len() - 1;
+        let predictions = &nodes[groups + last];
+        let loss = mse(predictions, targets);
+
+        // d(loss) / d(output) of every node - accumulated from all of the
+        // layers it feeds, starting with the network's outputs
+        let scale = 2.0 / predictions.len() as f32;
+        let mut errors: Vec<Vec<f32>> = nodes.iter().map(|node| vec![0.0; node.len()]).collect();
+
+        errors[groups + last] = predictions
+            .iter()
+            .zip(targets)
+            .map(|(prediction, target)| scale * (prediction - target))
+            .collect();
+
+        let mut gradients = Vec::with_capacity(self.layers.len());
+
+        // Layers are only fed by the ones before them, so by the time a
+        // layer is reached, all of its errors have been collected
+        for (idx, layer) in self.layers.iter().enumerate().rev() {
+            // d(loss) / d(weighted sum)
+            let deltas: Vec<f32> = errors[groups + idx]
+                .iter()
+                .zip(&sums[idx])
+                .map(|(error, &sum)| error * layer.activation.derivative(sum))
+                .collect();
+
+            gather(&nodes, &layer.sources, batch, &mut gathered);
+
+            let width = layer.row_width() + 1;
+            let mut layer_gradients = vec![0.0; layer.biases.len() * width];
+            let mut input_errors = vec![0.0; gathered.len()];
+
+            for ((deltas, inputs), input_errors) in deltas
+                .chunks_exact(layer.neurons)
+                .zip(gathered.chunks_exact(layer.inputs))
+                .zip(input_errors.chunks_exact_mut(layer.inputs))
+            {
+                // Each filter's weights are shared by all of its outputs, so
+                // they collect gradients from every cell
+                if let LayerKind::CircularConv { .. } = layer.kind {
+                    for ((gradient, deltas), row) in layer_gradients
+                        .chunks_exact_mut(width)
+                        .zip(deltas.chunks_exact(layer.inputs))
+                        .zip(layer.rows())
+                    {
+                        for (cell, delta) in deltas.iter().enumerate() {
+                            gradient[0] += delta;
+
+                            for (tap, weight) in row.iter().enumerate() {
+                                let input = layer.conv_input(cell, tap);
+
+                                gradient[1 + tap] += delta * inputs[input];
+                                input_errors[input] += delta * weight;
+                            }
+                        }
+                    }
+
+                    continue;
+                }
+
+                for ((gradient, delta), row) in layer_gradients
+                    .chunks_exact_mut(width)
+                    .zip(deltas)
+                    .zip(layer.rows())
+                {
+                    gradient[0] += delta;
+
+                    for (gradient, input) in gradient[1..].iter_mut().zip(inputs) {
+                        *gradient += delta * input;
+                    }
+
+                    for (error, weight) in input_errors.iter_mut().zip(row) {
+                        *error += delta * weight;
+                    }
+                }
+            }
+
+            gradients.push(layer_gradients);
+            scatter(&input_errors, &layer.sources, batch, &mut errors);
+        }
+
+        (loss, gradients.into_iter().rev().flatten().collect())
+    }
+
+    // Inverse of `weights()`, without re-allocating the layers; disabled
+    // connections stay at zero
+    fn set_params(&mut self, params: &[f32]) {
+        let mut params = params.iter();
+
+        for layer in &mut self.layers {
+            let width = layer.row_width();
+
+            for ((row, mask), bias) in layer
+                .weights
+                .chunks_exact_mut(width)
+                .zip(layer.mask.chunks_exact(width))
+                .zip(&mut layer.biases)
+            {
+                *bias = *params.next().unwrap();
+
+                for (weight, &enabled) in row.iter_mut().zip(mask) {
+                    let param = *params.next().unwrap();
+
+                    *weight = if enabled { param } else { 0.0 };
+                }
+            }
+        }
+    }
+}
+
+fn mse(outputs: &[f32], targets: &[f32]) -> f32 {
+    outputs
+        .iter()
+        .zip(targets)
+        .map(|(output, target)| (output - target).powi(2))
+        .sum::<f32>()
+        / outputs.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    // Hand-written controller to clone: steer towards the brighter half of
+    // the field of view
+    fn samples(rng: &mut ChaCha8Rng, count: usize) -> (Vec<f32>, Vec<f32>) {
+        let mut inputs = Vec::new();
+        let mut targets = Vec::new();
+
+        for _ in 0..count {
+            let vision: [f32; 4] = rng.gen();
+
+            targets.push(((vision[2] + vision[3]) - (vision[0] + vision[1])).tanh());
+            inputs.extend(vision);
+        }
+
+        (inputs, targets)
+    }
+
+    fn topology() -> [LayerTopology; 3] {
+        [
+            LayerTopology::new(4),
+            LayerTopology::new(8).with_activation(Activation::LeakyReLU(0.01)),
+            LayerTopology::new(1).with_activation(Activation::Tanh),
+        ]
+    }
+
+    fn network(rng: &mut ChaCha8Rng) -> Network {
+        Network::random_with(rng, &topology(), Initializer::Xavier)
+    }
+
+    // Compares `backpropagate()` with central differences of `loss()`, for a
+    // random network of given topology (with 4 inputs and 1 output)
+    fn assert_matches_numerical_gradients(topology: &[LayerTopology]) {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let network = Network::random_with(&mut rng, topology, Initializer::Xavier);
+        let (inputs, targets) = samples(&mut rng, 3);
+
+        let (_, gradients) = network.backpropagate(&inputs, &targets, 3);
+        let params = network.weights();
+
+        assert_eq!(gradients.len(), params.len());
+
+        for (idx, gradient) in gradients.iter().enumerate() {
+            let mut nudged = network.clone();
+            let mut shifted = params.clone();
+
+            shifted[idx] += 1e-2;
+            nudged.set_params(&shifted);
+            let above = nudged.loss(&inputs, &targets);
+
+            shifted[idx] -= 2e-2;
+            nudged.set_params(&shifted);
+            let below = nudged.loss(&inputs, &targets);
+
+            approx::assert_relative_eq!(*gradient, (above - below) / 2e-2, epsilon = 1e-3);
+        }
+    }
+
+    mod backpropagate {
+        use super::*;
+
+        #[test]
+        fn matches_numerical_gradients() {
+            assert_matches_numerical_gradients(&topology());
+        }
+    }
+
+    mod backpropagate_with_skip_connections {
+        use super::*;
+
+        #[test]
+        fn matches_numerical_gradients() {
+            assert_matches_numerical_gradients(&[
+                LayerTopology::new(3),
+                LayerTopology::input(1),
+                LayerTopology::new(5).with_activation(Activation::LeakyReLU(0.01)),
+                LayerTopology::new(4)
+                    .with_activation(Activation::Tanh)
+                    .with_sources([2, 0]),
+                LayerTopology::new(1)
+                    .with_activation(Activation::Tanh)
+                    .with_sources([1, 2, 3]),
+            ]);
+        }
+    }
+
+    mod backpropagate_with_convolutions {
+        use super::*;
+
+        #[test]
+        fn matches_numerical_gradients() {
+            assert_matches_numerical_gradients(&[
+                LayerTopology::new(4),
+                LayerTopology::new(8)
+                    .with_activation(Activation::LeakyReLU(0.01))
+                    .with_kind(LayerKind::CircularConv { kernel: 3 }),
+                LayerTopology::new(1).with_activation(Activation::Tanh),
+            ]);
+        }
+    }
+
+    mod train_epoch {
+        use super::*;
+
+        fn train(optimizer: &mut dyn Optimizer) -> (f32, f32) {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut network = network(&mut rng);
+            let (inputs, targets) = samples(&mut rng, 256);
+
+            let before = network.loss(&inputs, &targets);
+
+            for _ in 0..50 {
+                network.train_epoch(&inputs, &targets, 16, optimizer);
+            }
+
+            (before, network.loss(&inputs, &targets))
+        }
+
+        #[test]
+        fn sgd() {
+            let (before, after) = train(&mut Sgd::new(0.1));
+
+            assert!(after < before / 10.0, "{} -> {}", before, after);
+        }
+
+        #[test]
+        fn adam() {
+            let (before, after) = train(&mut Adam::new(0.01));
+
+            assert!(after < before / 10.0, "{} -> {}", before, after);
+        }
+
+        #[test]
+        fn returns_mean_loss() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut network = network(&mut rng);
+            let (inputs, targets) = samples(&mut rng, 10);
+            let expected = network.loss(&inputs, &targets);
+
+            // A single batch covering all of the samples is evaluated before
+            // the update
+            let actual = network.train_epoch(&inputs, &targets, 10, &mut Sgd::new(0.1));
+
+            approx::assert_relative_eq!(actual, expected);
+        }
+    }
+
+    mod set_params {
+        use super::*;
+
+        #[test]
+        fn roundtrip() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut network = network(&mut rng);
+            let params: Vec<f32> = (0..network.weights().len()).map(|n| n as f32).collect();
+
+            network.set_params(&params);
+
+            assert_eq!(network.weights(), params);
+        }
+    }
+}
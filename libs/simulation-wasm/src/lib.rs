@@ -9,6 +9,12 @@ pub struct RouletteSimulation {
     sim: sim::RouletteSimulation,
 }
 
+impl Default for RouletteSimulation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[wasm_bindgen]
 impl RouletteSimulation {
     #[wasm_bindgen(constructor)]
@@ -25,15 +31,23 @@ impl RouletteSimulation {
     pub fn step(&mut self) {
         self.sim.step(&mut self.rng);
     }
+    pub fn trace_brains(&mut self, enabled: bool) {
+        self.sim.trace_brains(enabled);
+    }
+    pub fn brain_traces(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(self.sim.brain_traces()).unwrap()
+    }
     pub fn train(&mut self) -> String {
         let stats = self.sim.train(&mut self.rng);
 
         format!(
-            "min={:.2}, max={:.2}, avg={:.2} median={:.2}",
+            "min={:.2}, max={:.2}, avg={:.2} median={:.2}, diversity={:.2}, discarded={}",
             stats.min_fitness(),
             stats.max_fitness(),
             stats.avg_fitness(),
-            stats.median_fitness()
+            stats.median_fitness(),
+            stats.diversity().unwrap_or_default(),
+            stats.discarded_children()
         )
     }
 }
@@ -44,6 +58,12 @@ pub struct RankSimulation{
     sim: sim::RankSimulation,
 }
 
+impl Default for RankSimulation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[wasm_bindgen]
 impl RankSimulation {
     #[wasm_bindgen(constructor)]
@@ -60,15 +80,23 @@ impl RankSimulation {
     pub fn step(&mut self) {
         self.sim.step(&mut self.rng);
     }
+    pub fn trace_brains(&mut self, enabled: bool) {
+        self.sim.trace_brains(enabled);
+    }
+    pub fn brain_traces(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(self.sim.brain_traces()).unwrap()
+    }
     pub fn train(&mut self) -> String {
         let stats = self.sim.train(&mut self.rng);
 
         format!(
-            "min={:.2}, max={:.2}, avg={:.2} median={:.2}",
+            "min={:.2}, max={:.2}, avg={:.2} median={:.2}, diversity={:.2}, discarded={}",
             stats.min_fitness(),
             stats.max_fitness(),
             stats.avg_fitness(),
-            stats.median_fitness()
+            stats.median_fitness(),
+            stats.diversity().unwrap_or_default(),
+            stats.discarded_children()
         )
     }
 }
@@ -14,8 +14,12 @@ pub struct Animal {
 
 impl Animal {
     pub fn random(rng: &mut dyn RngCore) -> Self {
+        Self::with_brain(rng, BrainKind::default())
+    }
+
+    pub fn with_brain(rng: &mut dyn RngCore, kind: BrainKind) -> Self {
         let eye = Eye::default();
-        let brain = Brain::random(rng, &eye);
+        let brain = Brain::random(rng, &eye, kind);
 
         Self::new(eye, brain, rng)
     }
@@ -49,13 +53,87 @@ impl Animal {
         self.rotation
     }
 
+    /// Runs this animal's brain over many vision frames at once (e.g. ones
+    /// collected from `World::vision_frames()`), returning the brain's
+    /// response to each of them, one after another.
+    ///
+    /// Panics if `frames` doesn't consist of whole frames, i.e. if its length
+    /// isn't a multiple of the number of the eye's cells.
+    pub fn evaluate(&self, frames: &[f32]) -> Vec<f32> {
+        let cells = self.eye.cells();
+
+        assert!(
+            frames.len().is_multiple_of(cells),
+            "got {} vision values, which is not a whole number of {}-cell frames",
+            frames.len(),
+            cells,
+        );
+
+        self.brain.propagate_batch(frames, frames.len() / cells)
+    }
+
+    /// Builds a brand new animal, so a plastic brain starts over from the
+    /// weights it has inherited - not from what its parent has learned.
     pub(crate) fn from_chromosome(
         chromosome: ga::Chromosome,
+        kind: BrainKind,
         rng: &mut dyn RngCore,
-    ) -> Self {
+    ) -> Result<Self, nn::NetworkError> {
         let eye = Eye::default();
-        let brain = Brain::from_chromosome(chromosome, &eye);
+        let brain = Brain::from_chromosome(chromosome, &eye, kind)?;
 
-        Self::new(eye, brain, rng)
+        Ok(Self::new(eye, brain, rng))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    mod evaluate {
+        use super::*;
+
+        #[test]
+        fn matches_stepping_through_frames() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut sim = RouletteSimulation::random(&mut rng);
+
+            let mut frames = sim.world().vision_frames();
+            sim.step(&mut rng);
+            frames.extend(sim.world().vision_frames());
+
+            let responses = sim.evaluate_brains(&frames);
+            let cells = Eye::default().cells();
+
+            assert_eq!(responses.len(), sim.world().animals().len());
+
+            for (animal, response) in sim.world().animals().iter().zip(&responses) {
+                let mut brain = animal.brain.clone();
+
+                let expected: Vec<f32> = frames
+                    .chunks_exact(cells)
+                    .flat_map(|frame| {
+                        let (speed, rotation) = brain.propagate(frame, &mut nn::Scratch::new());
+
+                        [speed, rotation]
+                    })
+                    .collect();
+
+                assert_eq!(response, &expected);
+            }
+        }
+
+        #[test]
+        #[should_panic(expected = "not a whole number of 9-cell frames")]
+        fn rejects_partial_frames() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let sim = RouletteSimulation::random(&mut rng);
+            let mut frames = sim.world().vision_frames();
+
+            frames.pop();
+            sim.evaluate_brains(&frames);
+        }
     }
-}
\ No newline at end of file
+}
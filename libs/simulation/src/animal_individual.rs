@@ -21,18 +21,66 @@ impl ga::Individual for AnimalIndividual {
     fn fitness(&self) -> f32 {
         self.fitness
     }
+
+    /// Compares the brains' weights the way `nn::Network::weight_distance()`
+    /// does, so that diversity and fitness sharing see the same thing as the
+    /// network itself - including disabled connections counting as zero.
+    fn distance(&self, other: &Self) -> f32 {
+        nn::WeightDistance::default().measure(&self.weights(), &other.weights())
+    }
 }
 
 impl AnimalIndividual {
     pub fn from_animal(animal: &Animal) -> Self {
         Self {
             fitness: animal.satiation as f32,
-            chromosome: animal.brain.as_chromosome(),
+            chromosome: animal.as_chromosome(),
         }
     }
 
-    pub fn into_animal(self, rng: &mut dyn RngCore) -> Animal {
-        Animal::from_chromosome(self.chromosome, rng)
+    pub fn into_animal(
+        self,
+        kind: BrainKind,
+        rng: &mut dyn RngCore,
+    ) -> Result<Animal, nn::NetworkError> {
+        Animal::from_chromosome(self.chromosome, kind, rng)
+    }
+
+    /// Genes as the brain sees them - masked ones are zeroed by
+    /// `nn::Network::set_mask()`, whatever their value in the chromosome.
+    fn weights(&self) -> Vec<f32> {
+        let mask = self.chromosome.mask();
+
+        self.chromosome
+            .iter()
+            .enumerate()
+            .map(|(idx, &gene)| match mask {
+                Some(mask) if !mask[idx] => 0.0,
+                _ => gene,
+            })
+            .collect()
     }
+}
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ga::Individual;
+
+    mod distance {
+        use super::*;
+
+        #[test]
+        fn ignores_masked_genes() {
+            let a = AnimalIndividual::create(
+                ga::Chromosome::from_iter([1.0, 2.0, 3.0]).with_mask(vec![true, false, true]),
+            );
+
+            let b = AnimalIndividual::create(
+                ga::Chromosome::from_iter([1.0, -5.0, 7.0]).with_mask(vec![true, false, true]),
+            );
+
+            assert_eq!(a.distance(&b), 4.0);
+        }
+    }
 }
\ No newline at end of file
@@ -1,44 +1,248 @@
 use ga::Chromosome;
+use nn::Scalar;
 
 use crate::*;
 
+/// Which kind of neural network steers an animal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BrainKind {
+    /// Stateless, layered `nn::Network`
+    #[default]
+    FeedForward,
+
+    /// `nn::Ctrnn`, whose neurons carry their state from one step to
+    /// another - makes for smoother steering
+    Ctrnn,
+
+    /// Same as `FeedForward`, but computing in `nn::Fixed` - so that the
+    /// brain's responses are bit-identical on every platform (e.g. when
+    /// replaying a native run in the browser)
+    FixedPoint,
+
+    /// `nn::PlasticNetwork` - evolves its initial weights along with rules
+    /// of how they change during the animal's life; whatever got learned
+    /// is not inherited, since offspring come from `Animal::from_chromosome()`
+    Plastic,
+
+    /// Like `FeedForward`, but with a circular convolution looking at the
+    /// eye's cells - so that a pattern learned on one side of the field of
+    /// view is recognized on the other one as well
+    Convolutional,
+}
+
 #[derive(Debug, Clone)]
 pub struct Brain {
-    pub(crate) nn: nn::Network,
+    pub(crate) controller: Controller,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Controller {
+    FeedForward(nn::Network),
+    Ctrnn(nn::Ctrnn),
+    FixedPoint(nn::Network<nn::Fixed>),
+    Plastic(nn::PlasticNetwork),
+    Convolutional(nn::Network),
 }
 
 impl Brain {
-    pub fn random(rng: &mut dyn RngCore, eye: &Eye) -> Self {
-        Self {
-            nn: nn::Network::random(rng, &Self::topology(eye)),
+    pub fn random(rng: &mut dyn RngCore, eye: &Eye, kind: BrainKind) -> Self {
+        Self::random_with(rng, eye, kind, nn::Initializer::default())
+    }
+
+    /// Like `random()`, but draws the initial weights of layered brains
+    /// using given initializer - e.g. `He` keeps the ReLU hidden layer from
+    /// saturating for larger eyes; `Ctrnn` and `Plastic` brains ignore it.
+    pub fn random_with(
+        rng: &mut dyn RngCore,
+        eye: &Eye,
+        kind: BrainKind,
+        initializer: nn::Initializer,
+    ) -> Self {
+        let controller = match kind {
+            BrainKind::FeedForward => {
+                Controller::FeedForward(Self::random_network(rng, eye, initializer))
+            }
+            BrainKind::Ctrnn => {
+                Controller::Ctrnn(nn::Ctrnn::random(rng, &Self::ctrnn_topology(eye)))
+            }
+            BrainKind::FixedPoint => {
+                Controller::FixedPoint(Self::random_network(rng, eye, initializer).cast())
+            }
+            BrainKind::Plastic => {
+                Controller::Plastic(nn::PlasticNetwork::random(rng, &Self::plastic_topology(eye)))
+            }
+            BrainKind::Convolutional => Controller::Convolutional(nn::Network::random_with(
+                rng,
+                &Self::conv_topology(eye),
+                initializer,
+            )),
+        };
+
+        Self { controller }
+    }
+
+    pub fn kind(&self) -> BrainKind {
+        match self.controller {
+            Controller::FeedForward(_) => BrainKind::FeedForward,
+            Controller::Convolutional(_) => BrainKind::Convolutional,
+            Controller::Ctrnn(_) => BrainKind::Ctrnn,
+            Controller::FixedPoint(_) => BrainKind::FixedPoint,
+            Controller::Plastic(_) => BrainKind::Plastic,
         }
     }
 
     pub(crate) fn as_chromosome(&self) -> ga::Chromosome {
-        Chromosome::from_iter(self.nn.weights())
+        match &self.controller {
+            Controller::FeedForward(nn) | Controller::Convolutional(nn) => {
+                Chromosome::from_iter(nn.weights()).with_mask(nn.mask())
+            }
+            Controller::Ctrnn(ctrnn) => Chromosome::from_iter(ctrnn.weights()),
+
+            // Every `Fixed` weight within <-256, 256> fits into `f32`
+            // exactly, so this round-trips through `from_chromosome()`
+            Controller::FixedPoint(nn) => nn
+                .weights()
+                .into_iter()
+                .map(nn::Fixed::to_f32)
+                .collect::<Chromosome>()
+                .with_mask(nn.mask()),
+
+            // Initial weights, not the learned ones
+            Controller::Plastic(nn) => Chromosome::from_iter(nn.weights()),
+        }
     }
 
     pub(crate) fn from_chromosome(
         chromosome: ga::Chromosome,
         eye: &Eye,
-    ) -> Self {
-        Self {
-            nn: nn::Network::from_weights(
+        kind: BrainKind,
+    ) -> Result<Self, nn::NetworkError> {
+        let controller = match kind {
+            BrainKind::FeedForward => Controller::FeedForward(Self::network_from_chromosome(
+                chromosome,
                 &Self::topology(eye),
+            )?),
+            BrainKind::Ctrnn => Controller::Ctrnn(nn::Ctrnn::try_from_weights(
+                &Self::ctrnn_topology(eye),
                 chromosome,
+            )?),
+            BrainKind::FixedPoint => Controller::FixedPoint(
+                Self::network_from_chromosome(chromosome, &Self::topology(eye))?.cast(),
             ),
+            BrainKind::Convolutional => Controller::Convolutional(Self::network_from_chromosome(
+                chromosome,
+                &Self::conv_topology(eye),
+            )?),
+            BrainKind::Plastic => Controller::Plastic(nn::PlasticNetwork::try_from_weights(
+                &Self::plastic_topology(eye),
+                chromosome,
+            )?),
+        };
+
+        Ok(Self { controller })
+    }
+
+    /// Responds to a single vision frame with `(speed, rotation)`,
+    /// advancing the brain's state (if it's got any).
+    pub(crate) fn propagate(&mut self, vision: &[f32], scratch: &mut nn::Scratch) -> (f32, f32) {
+        let response = match &mut self.controller {
+            Controller::FeedForward(nn) | Controller::Convolutional(nn) => {
+                nn.propagate_into(vision, scratch)
+            }
+            Controller::Ctrnn(ctrnn) => ctrnn.propagate_into(vision, scratch),
+            Controller::Plastic(nn) => nn.propagate_into(vision, scratch),
+            Controller::FixedPoint(nn) => {
+                let response = nn.propagate(to_fixed(vision));
+
+                return (response[0].to_f32(), response[1].to_f32());
+            }
+        };
+
+        (response[0], response[1])
+    }
+
+    /// Like `propagate()`, but records what every neuron was doing; the
+    /// response is made of the last two outputs of the trace.
+    ///
+    /// Layered brains have got no recurrent layers, so they're traced
+    /// without carrying any state over.
+    pub(crate) fn propagate_traced(&mut self, vision: Vec<f32>) -> nn::Trace {
+        match &mut self.controller {
+            Controller::FeedForward(nn) | Controller::Convolutional(nn) => {
+                nn.propagate_traced(vision, &mut nn::Scratch::new())
+            }
+            Controller::Ctrnn(ctrnn) => ctrnn.propagate_traced(vision),
+            Controller::FixedPoint(nn) => {
+                nn.propagate_traced(to_fixed(&vision), &mut nn::Scratch::new())
+            }
+            Controller::Plastic(nn) => nn.propagate_traced(vision),
+        }
+    }
+
+    pub(crate) fn propagate_batch(&self, frames: &[f32], batch: usize) -> Vec<f32> {
+        match &self.controller {
+            Controller::FeedForward(nn) | Controller::Convolutional(nn) => {
+                nn.propagate_batch(frames, batch)
+            }
+            Controller::Ctrnn(ctrnn) => ctrnn.propagate_batch(frames, batch),
+            Controller::FixedPoint(nn) => nn
+                .propagate_batch(&to_fixed(frames), batch)
+                .into_iter()
+                .map(nn::Fixed::to_f32)
+                .collect(),
+            Controller::Plastic(nn) => nn.propagate_batch(frames, batch),
         }
     }
 
+    fn random_network(
+        rng: &mut dyn RngCore,
+        eye: &Eye,
+        initializer: nn::Initializer,
+    ) -> nn::Network {
+        nn::Network::random_with(rng, &Self::topology(eye), initializer)
+    }
+
+    fn network_from_chromosome(
+        chromosome: ga::Chromosome,
+        topology: &[nn::LayerTopology],
+    ) -> Result<nn::Network, nn::NetworkError> {
+        let mask = chromosome.mask().map(<[bool]>::to_vec);
+
+        nn::Network::try_from_masked_weights(topology, chromosome, mask.as_deref())
+    }
+
     fn topology(eye: &Eye) -> [nn::LayerTopology; 3] {
         [
-            nn::LayerTopology {
-                neurons: eye.cells(),
-            },
-            nn::LayerTopology {
-                neurons: 2 * eye.cells(),
-            },
-            nn::LayerTopology { neurons: 2 },
+            nn::LayerTopology::new(eye.cells()),
+            nn::LayerTopology::new(2 * eye.cells()),
+            // tanh, so that the birds can steer both left and right
+            nn::LayerTopology::new(2).with_activation(nn::Activation::Tanh),
         ]
     }
-}
\ No newline at end of file
+
+    // Two filters, so the hidden layer is as wide as the feed-forward one's
+    fn conv_topology(eye: &Eye) -> [nn::LayerTopology; 3] {
+        let kernel = 3.min(eye.cells());
+
+        [
+            nn::LayerTopology::new(eye.cells()),
+            nn::LayerTopology::new(2 * eye.cells())
+                .with_kind(nn::LayerKind::CircularConv { kernel }),
+            nn::LayerTopology::new(2).with_activation(nn::Activation::Tanh),
+        ]
+    }
+
+    fn plastic_topology(eye: &Eye) -> nn::PlasticTopology {
+        nn::PlasticTopology::new(Self::topology(eye))
+    }
+
+    // Same number of neurons as the feed-forward brain's hidden layer, the
+    // last two of which steer
+    fn ctrnn_topology(eye: &Eye) -> nn::CtrnnTopology {
+        nn::CtrnnTopology::new(eye.cells(), 2 * eye.cells(), 2)
+    }
+}
+
+fn to_fixed(values: &[f32]) -> Vec<nn::Fixed> {
+    values.iter().map(|&value| nn::Fixed::from_f32(value)).collect()
+}
@@ -103,20 +103,6 @@ impl Default for Eye {
     }
 }
 
-impl Eye {
-    fn new_ex(fov_range: f32, fov_angle: f32, cells: usize) -> Self {
-        assert!(fov_range > 0.0);
-        assert!(fov_angle > 0.0);
-        assert!(cells > 0);
-
-        Self {
-            fov_range,
-            fov_angle,
-            cells,
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -141,7 +127,7 @@ mod tests {
 
     impl TestCase {
         fn run(self) {
-            let eye = Eye::new_ex(self.fov_range, self.fov_angle, TEST_EYE_CELLS);
+            let eye = Eye::new(self.fov_range, self.fov_angle, TEST_EYE_CELLS);
 
             let actual = eye.process_vision(
                 na::Point2::new(self.x, self.y),
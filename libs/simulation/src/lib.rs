@@ -25,26 +25,64 @@ pub struct RouletteSimulation {
     world: World,
     ga: ga::GeneticAlgorithm<ga::RouletteWheelSelection>,
     age: usize,
+    brain_kind: BrainKind,
+    scratch: nn::Scratch,
+
+    // [step][animal], recorded only when enabled with `trace_brains()`
+    traces: Option<Vec<Vec<nn::Trace>>>,
 }
 
 impl RouletteSimulation {
     pub fn random(rng: &mut dyn RngCore) -> Self {
-        let world = World::random(rng);
+        Self::with_brain(rng, BrainKind::default())
+    }
+
+    pub fn with_brain(rng: &mut dyn RngCore, brain_kind: BrainKind) -> Self {
+        let world = World::with_brain(rng, brain_kind);
 
         let ga = ga::GeneticAlgorithm::new(
             ga::RouletteWheelSelection::new(),
             ga::UniformCrossover::new(),
             ga::GaussianMutation::new(0.01, 0.3),
-        );
+        )
+        // Reported by the web UI after each generation
+        .with_diversity();
 
-        Self { world, ga, age: 0 }
+        Self { world, ga, age: 0, brain_kind, scratch: nn::Scratch::new(), traces: None }
     }
     
     pub fn world(&self) -> &World {
         &self.world
     }
 
+    /// Scores every animal's brain against the same recorded vision frames
+    /// (see `World::vision_frames()`), returning each animal's responses.
+    pub fn evaluate_brains(&self, frames: &[f32]) -> Vec<Vec<f32>> {
+        self.world
+            .animals
+            .iter()
+            .map(|animal| animal.evaluate(frames))
+            .collect()
+    }
+
+    /// Starts (or stops) recording what every animal's brain does at each
+    /// step - see `brain_traces()`.
+    pub fn trace_brains(&mut self, enabled: bool) {
+        self.traces = if enabled { Some(Vec::new()) } else { None };
+    }
+
+    /// Brain traces recorded during the current generation (or the one
+    /// that has just been evolved, if the new one hasn't started yet),
+    /// indexed by step and then by animal.
+    pub fn brain_traces(&self) -> &[Vec<nn::Trace>] {
+        self.traces.as_deref().unwrap_or_default()
+    }
+
     pub fn step(&mut self, rng: &mut dyn RngCore) -> Option<ga::Statistics> {
+        if let (0, Some(traces)) = (self.age, &mut self.traces) {
+            traces.clear();
+        }
+
         self.process_collisions(rng);
         self.process_brains();
         self.process_movements();
@@ -96,6 +134,8 @@ impl RouletteSimulation {
     }
 
     fn process_brains(&mut self) {
+        let mut step_traces = self.traces.as_ref().map(|_| Vec::new());
+
         for animal in &mut self.world.animals {
             let vision = animal.eye.process_vision(
                 animal.position,
@@ -103,14 +143,24 @@ impl RouletteSimulation {
                 &self.world.foods,
             );
 
-            let response = animal.brain.nn.propagate(vision);
+            let (speed, rotation) = match &mut step_traces {
+                Some(step_traces) => {
+                    let trace = animal.brain.propagate_traced(vision);
+                    let outputs = trace.outputs();
+                    let response = (outputs[outputs.len() - 2], outputs[outputs.len() - 1]);
+
+                    step_traces.push(trace);
+                    response
+                }
+                None => animal.brain.propagate(&vision, &mut self.scratch),
+            };
 
-            let speed = response[0].clamp(
+            let speed = speed.clamp(
                 -SPEED_ACCEL,
                 SPEED_ACCEL,
             );
 
-            let rotation = response[1].clamp(
+            let rotation = rotation.clamp(
                 -ROTATION_ACCEL,
                 ROTATION_ACCEL,
             );
@@ -125,35 +175,15 @@ impl RouletteSimulation {
             // (btw, there is no need for ROTATION_MIN or ROTATION_MAX,
             // because rotation automatically wraps from 2*PI back to 0 
         }
+
+        if let (Some(traces), Some(step_traces)) = (&mut self.traces, step_traces) {
+            traces.push(step_traces);
+        }
     }
 
     fn evolve(&mut self, rng: &mut dyn RngCore) -> ga::Statistics {
         self.age = 0;
-    
-        // Transforms `Vec<Animal>` to `Vec<AnimalIndividual>`
-        let mut current_population: Vec<_> = self
-            .world
-            .animals
-            .iter()
-            .map(AnimalIndividual::from_animal)
-            .collect();
-    
-        // Evolves this `Vec<AnimalIndividual>`
-        let (evolved_population, stats) = self.ga.evolve(
-            rng,
-            &mut current_population,
-        );
-    
-        // Transforms `Vec<AnimalIndividual>` back into `Vec<Animal>`
-        self.world.animals = evolved_population
-            .into_iter()
-            .map(|individual| individual.into_animal(rng))
-            .collect();
-    
-        for food in &mut self.world.foods {
-            food.position = rng.gen();
-        }
-        stats
+        self.world.evolve(&mut self.ga, self.brain_kind, rng)
     }
     
 
@@ -164,26 +194,64 @@ pub struct RankSimulation {
     world: World,
     ga: ga::GeneticAlgorithm<ga::RankSelection>,
     age: usize,
+    brain_kind: BrainKind,
+    scratch: nn::Scratch,
+
+    // [step][animal], recorded only when enabled with `trace_brains()`
+    traces: Option<Vec<Vec<nn::Trace>>>,
 }
 
 impl RankSimulation {
     pub fn random(rng: &mut dyn RngCore) -> Self {
-        let world = World::random(rng);
+        Self::with_brain(rng, BrainKind::default())
+    }
+
+    pub fn with_brain(rng: &mut dyn RngCore, brain_kind: BrainKind) -> Self {
+        let world = World::with_brain(rng, brain_kind);
 
         let ga = ga::GeneticAlgorithm::new(
             ga::RankSelection::new(),
             ga::UniformCrossover::new(),
             ga::GaussianMutation::new(0.01, 0.3),
-        );
+        )
+        // Reported by the web UI after each generation
+        .with_diversity();
 
-        Self { world, ga, age: 0 }
+        Self { world, ga, age: 0, brain_kind, scratch: nn::Scratch::new(), traces: None }
     }
     
     pub fn world(&self) -> &World {
         &self.world
     }
 
+    /// Scores every animal's brain against the same recorded vision frames
+    /// (see `World::vision_frames()`), returning each animal's responses.
+    pub fn evaluate_brains(&self, frames: &[f32]) -> Vec<Vec<f32>> {
+        self.world
+            .animals
+            .iter()
+            .map(|animal| animal.evaluate(frames))
+            .collect()
+    }
+
+    /// Starts (or stops) recording what every animal's brain does at each
+    /// step - see `brain_traces()`.
+    pub fn trace_brains(&mut self, enabled: bool) {
+        self.traces = if enabled { Some(Vec::new()) } else { None };
+    }
+
+    /// Brain traces recorded during the current generation (or the one
+    /// that has just been evolved, if the new one hasn't started yet),
+    /// indexed by step and then by animal.
+    pub fn brain_traces(&self) -> &[Vec<nn::Trace>] {
+        self.traces.as_deref().unwrap_or_default()
+    }
+
     pub fn step(&mut self, rng: &mut dyn RngCore) -> Option<ga::Statistics> {
+        if let (0, Some(traces)) = (self.age, &mut self.traces) {
+            traces.clear();
+        }
+
         self.process_collisions(rng);
         self.process_brains();
         self.process_movements();
@@ -235,6 +303,8 @@ impl RankSimulation {
     }
 
     fn process_brains(&mut self) {
+        let mut step_traces = self.traces.as_ref().map(|_| Vec::new());
+
         for animal in &mut self.world.animals {
             let vision = animal.eye.process_vision(
                 animal.position,
@@ -242,14 +312,24 @@ impl RankSimulation {
                 &self.world.foods,
             );
 
-            let response = animal.brain.nn.propagate(vision);
+            let (speed, rotation) = match &mut step_traces {
+                Some(step_traces) => {
+                    let trace = animal.brain.propagate_traced(vision);
+                    let outputs = trace.outputs();
+                    let response = (outputs[outputs.len() - 2], outputs[outputs.len() - 1]);
+
+                    step_traces.push(trace);
+                    response
+                }
+                None => animal.brain.propagate(&vision, &mut self.scratch),
+            };
 
-            let speed = response[0].clamp(
+            let speed = speed.clamp(
                 -SPEED_ACCEL,
                 SPEED_ACCEL,
             );
 
-            let rotation = response[1].clamp(
+            let rotation = rotation.clamp(
                 -ROTATION_ACCEL,
                 ROTATION_ACCEL,
             );
@@ -264,35 +344,15 @@ impl RankSimulation {
             // (btw, there is no need for ROTATION_MIN or ROTATION_MAX,
             // because rotation automatically wraps from 2*PI back to 0 
         }
+
+        if let (Some(traces), Some(step_traces)) = (&mut self.traces, step_traces) {
+            traces.push(step_traces);
+        }
     }
 
     fn evolve(&mut self, rng: &mut dyn RngCore) -> ga::Statistics {
         self.age = 0;
-    
-        // Transforms `Vec<Animal>` to `Vec<AnimalIndividual>`
-        let mut current_population: Vec<_> = self
-            .world
-            .animals
-            .iter()
-            .map(AnimalIndividual::from_animal)
-            .collect();
-    
-        // Evolves this `Vec<AnimalIndividual>`
-        let (evolved_population, stats) = self.ga.evolve(
-            rng,
-            &mut current_population,
-        );
-    
-        // Transforms `Vec<AnimalIndividual>` back into `Vec<Animal>`
-        self.world.animals = evolved_population
-            .into_iter()
-            .map(|individual| individual.into_animal(rng))
-            .collect();
-    
-        for food in &mut self.world.foods {
-            food.position = rng.gen();
-        }
-        stats
+        self.world.evolve(&mut self.ga, self.brain_kind, rng)
     }
     
 
@@ -8,8 +8,13 @@ pub struct World {
 
 impl World {
     pub fn random(rng: &mut dyn RngCore) -> Self {
+        Self::with_brain(rng, BrainKind::default())
+    }
+
+    /// Like `random()`, but every animal gets the given kind of brain.
+    pub fn with_brain(rng: &mut dyn RngCore, kind: BrainKind) -> Self {
         let animals = (0..40)
-            .map(|_| Animal::random(rng))
+            .map(|_| Animal::with_brain(rng, kind))
             .collect();
 
         let foods = (0..60)
@@ -29,4 +34,100 @@ impl World {
     pub fn foods(&self) -> &[Food] {
         &self.foods
     }
+
+    /// What every animal currently sees, laid one after another - record
+    /// these over a few steps to score brains with `Animal::evaluate()`.
+    pub fn vision_frames(&self) -> Vec<f32> {
+        self.animals
+            .iter()
+            .flat_map(|animal| {
+                animal
+                    .eye
+                    .process_vision(animal.position, animal.rotation, &self.foods)
+            })
+            .collect()
+    }
+
+    /// Replaces the animals with the next generation bred by given genetic
+    /// algorithm, and scatters the food anew.
+    ///
+    /// Chromosomes that don't make up a valid brain (e.g. ones with a NaN
+    /// gene) are replaced with fresh, random birds; the statistics report
+    /// how many of them there were as `discarded_children()`.
+    pub(crate) fn evolve<S: ga::SelectionMethod>(
+        &mut self,
+        ga: &mut ga::GeneticAlgorithm<S>,
+        brain_kind: BrainKind,
+        rng: &mut dyn RngCore,
+    ) -> ga::Statistics {
+        // Transforms `Vec<Animal>` to `Vec<AnimalIndividual>`
+        let current_population: Vec<_> = self
+            .animals
+            .iter()
+            .map(AnimalIndividual::from_animal)
+            .collect();
+
+        // Evolves this `Vec<AnimalIndividual>`
+        let (evolved_population, stats) = ga.evolve(rng, &current_population);
+        let mut discarded = 0;
+
+        // Transforms `Vec<AnimalIndividual>` back into `Vec<Animal>`
+        self.animals = evolved_population
+            .into_iter()
+            .map(|individual| match individual.into_animal(brain_kind, rng) {
+                Ok(animal) => animal,
+                Err(_) => {
+                    discarded += 1;
+                    Animal::with_brain(rng, brain_kind)
+                }
+            })
+            .collect();
+
+        for food in &mut self.foods {
+            food.position = rng.gen();
+        }
+
+        stats.with_discarded_children(discarded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    mod evolve {
+        use super::*;
+
+        fn ga(coeff: f32) -> ga::GeneticAlgorithm<ga::RankSelection> {
+            ga::GeneticAlgorithm::new(
+                ga::RankSelection::new(),
+                ga::UniformCrossover::new(),
+                ga::GaussianMutation::new(1.0, coeff),
+            )
+        }
+
+        #[test]
+        fn test() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut world = World::random(&mut rng);
+            let stats = world.evolve(&mut ga(0.3), BrainKind::default(), &mut rng);
+
+            assert_eq!(world.animals().len(), 40);
+            assert_eq!(stats.discarded_children(), 0);
+        }
+
+        #[test]
+        fn counts_discarded_children() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut world = World::random(&mut rng);
+
+            // Every gene mutates into an infinity, so no brain can be built
+            let stats = world.evolve(&mut ga(f32::INFINITY), BrainKind::default(), &mut rng);
+
+            assert_eq!(world.animals().len(), 40);
+            assert_eq!(stats.discarded_children(), 40);
+        }
+    }
 }
\ No newline at end of file